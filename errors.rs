@@ -3,6 +3,7 @@ use ink::{
     prelude::{format, string::String},
     LangError,
 };
+use openbrush::contracts::traits::psp22::PSP22Error;
 
 #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
@@ -10,6 +11,7 @@ pub enum AzTokenSaleToAirdropError {
     ContractCall(LangError),
     InkEnvError(String),
     NotFound(String),
+    PSP22Error(PSP22Error),
     Unauthorised,
     UnprocessableEntity(String),
 }
@@ -23,3 +25,8 @@ impl From<LangError> for AzTokenSaleToAirdropError {
         AzTokenSaleToAirdropError::ContractCall(e)
     }
 }
+impl From<PSP22Error> for AzTokenSaleToAirdropError {
+    fn from(error: PSP22Error) -> Self {
+        AzTokenSaleToAirdropError::PSP22Error(error)
+    }
+}
@@ -6,18 +6,231 @@ mod errors;
 mod az_token_sale_to_airdrop {
     use crate::errors::AzTokenSaleToAirdropError;
     use ink::{
+        codegen::EmitEvent,
         env::{
             call::{build_call, Call, ExecutionInput, Selector},
+            hash::Blake2x256,
             CallFlags,
         },
-        prelude::string::{String, ToString},
+        prelude::{
+            string::{String, ToString},
+            vec::Vec,
+        },
+        reflect::ContractEventBase,
         storage::Mapping,
     };
+    use openbrush::contracts::traits::psp22::PSP22Error;
     use primitive_types::U256;
 
     // === TYPES ===
+    type Event = <AzTokenSaleToAirdrop as ContractEventBase>::Type;
+    // (payee, asset) where asset None is AZERO
+    type PayeeAsset = (AccountId, Option<AccountId>);
     type Result<T> = core::result::Result<T, AzTokenSaleToAirdropError>;
 
+    // === CONSTANTS ===
+    const BASIS_POINTS_TOTAL: u16 = 10_000;
+
+    // === ENUMS ===
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Status {
+        Pending,
+        Whitelist,
+        Public,
+        SoldOut,
+        Ended,
+        Finalized,
+    }
+
+    // Basis points given to the referrer on each referred buy
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum ReferralBonus {
+        // Extra out tokens registered on the airdrop contract, on top of the buyer's out amount
+        Out(u16),
+        // Commission carved from the buyer's in amount
+        In(u16),
+    }
+
+    // === EVENTS ===
+    #[ink(event)]
+    pub struct AdminTransferAccept {
+        #[ink(topic)]
+        previous_admin: AccountId,
+        #[ink(topic)]
+        admin: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct AdminTransferCancel {
+        #[ink(topic)]
+        pending_admin: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct AdminTransferPropose {
+        #[ink(topic)]
+        admin: AccountId,
+        #[ink(topic)]
+        pending_admin: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct BonusScheduleUpdate {
+        bonus_schedule: Option<BonusSchedule>,
+    }
+
+    #[ink(event)]
+    pub struct Buy {
+        #[ink(topic)]
+        buyer: AccountId,
+        // Differs from buyer when bought on their behalf
+        #[ink(topic)]
+        payer: AccountId,
+        // None when paid in the asset the sale is denominated in
+        payment_asset: Option<AccountId>,
+        in_amount: Balance,
+        out_amount: Balance,
+        // Registered on the airdrop contract on top of out_amount
+        out_bonus: Balance,
+        refund_amount: Balance,
+        in_raised: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Referral {
+        #[ink(topic)]
+        referrer: AccountId,
+        #[ink(topic)]
+        buyer: AccountId,
+        in_amount: Balance,
+        in_commission: Balance,
+        out_bonus: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ReferralBonusUpdate {
+        referral_bonus: Option<ReferralBonus>,
+    }
+
+    #[ink(event)]
+    pub struct Refund {
+        #[ink(topic)]
+        buyer: AccountId,
+        in_amount: Balance,
+        out_amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Withdraw {
+        #[ink(topic)]
+        to: AccountId,
+        payment_asset: Option<AccountId>,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ScheduleUpdate {
+        start: Timestamp,
+        end: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct OffchainPurchase {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        in_equivalent: Balance,
+        out_amount: Balance,
+        reference: [u8; 32],
+    }
+
+    #[ink(event)]
+    pub struct OperatorUpdate {
+        operator: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct Pause {
+        reason: Option<String>,
+    }
+
+    #[ink(event)]
+    pub struct Unpause {}
+
+    #[ink(event)]
+    pub struct PaymentAssetAdd {
+        #[ink(topic)]
+        token: AccountId,
+        in_unit: Balance,
+        in_target: Option<Balance>,
+    }
+
+    #[ink(event)]
+    pub struct PaymentAssetRemove {
+        #[ink(topic)]
+        token: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct VestingTermsUpdate {
+        vesting_terms: Option<VestingTerms>,
+        whitelist_vesting_terms: Option<VestingTerms>,
+    }
+
+    #[ink(event)]
+    pub struct WhitelistAdd {
+        #[ink(topic)]
+        address: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct WhitelistRemove {
+        #[ink(topic)]
+        address: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct WhitelistMerkleRootUpdate {
+        whitelist_merkle_root: Option<[u8; 32]>,
+    }
+
+    // Lifecycle events are emitted lazily by the first call that observes the transition,
+    // so Ended is emitted by the first claim_refund, withdraw, finalize or whitelist change after end
+    #[ink(event)]
+    pub struct WhitelistPhaseStart {
+        timestamp: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct PublicPhaseStart {
+        timestamp: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct SoldOut {
+        timestamp: Timestamp,
+        in_raised: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Ended {
+        timestamp: Timestamp,
+        in_raised: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Finalize {
+        timestamp: Timestamp,
+        in_raised: Balance,
+    }
+
     // === STRUCTS ===
     #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq)]
     #[cfg_attr(
@@ -26,13 +239,40 @@ mod az_token_sale_to_airdrop {
     )]
     pub struct Buyer {
         pub total_in: Balance,
+        pub total_out: Balance,
         pub whitelisted: bool,
+        pub whitelist_allocation: Option<Balance>,
+        // Amounts paid per payment asset, also included in total_in as base equivalents
+        pub payment_assets_in: Vec<(AccountId, Balance)>,
+        // Amounts paid on the buyer's behalf per payer, also included in total_in
+        pub payers_in: Vec<(AccountId, Balance)>,
+        // Set by the first referred buy, later referred buys must use the same referrer
+        pub referrer: Option<AccountId>,
+        // Credited to the referrer by this buyer's referred buys, unwound on refund
+        pub referred_in: Balance,
+        pub referrer_in_commission: Balance,
+        pub referrer_out_bonus: Balance,
+        // Recorded by admin for purchases paid off-chain, also included in total_in
+        pub offchain_in: Balance,
     }
 
     #[derive(Debug, Clone, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub struct Config {
         pub admin: AccountId,
+        pub pending_admin: Option<AccountId>,
+        pub operator: Option<AccountId>,
+        pub treasury: AccountId,
+        pub payees: Vec<(AccountId, u16)>,
+        pub hold_proceeds: bool,
+        pub paused: bool,
+        pub pause_reason: Option<String>,
+        pub finalized_at: Option<Timestamp>,
+        pub final_in_raised: Option<Balance>,
+        pub vesting_terms: Option<VestingTerms>,
+        pub whitelist_vesting_terms: Option<VestingTerms>,
+        pub referral_bonus: Option<ReferralBonus>,
+        pub bonus_schedule: Option<BonusSchedule>,
         pub airdrop_smart_contract: AccountId,
         pub in_unit: Balance,
         pub out_unit: Balance,
@@ -41,6 +281,125 @@ mod az_token_sale_to_airdrop {
         pub whitelist_duration: Timestamp,
         pub in_target: Balance,
         pub in_raised: Balance,
+        pub min_in_per_buyer: Balance,
+        pub max_in_per_buyer: Option<Balance>,
+        pub whitelist_in_target: Option<Balance>,
+        pub whitelist_in_raised: Balance,
+        pub public_in_raised: Balance,
+        // Included in in_raised
+        pub offchain_in_raised: Balance,
+        pub in_soft_cap: Option<Balance>,
+        pub in_refunded: Balance,
+        pub in_withdrawn: Balance,
+        pub whitelist_merkle_root: Option<[u8; 32]>,
+        pub in_token: Option<AccountId>,
+        pub payment_assets: Vec<(AccountId, PaymentAsset)>,
+        pub price_tiers: Vec<PriceTier>,
+        pub price_tier_index: Option<u32>,
+        pub price_tier_in_remaining: Balance,
+        pub out_unit_end: Option<Balance>,
+        pub price_step_duration: Timestamp,
+        pub current_out_unit: Balance,
+    }
+
+    // Additional PSP22 token accepted as payment, where in_unit buys the same as the sale's in_unit
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct PaymentAsset {
+        pub in_unit: Balance,
+        pub in_target: Option<Balance>,
+        pub in_raised: Balance,
+        pub in_withdrawn: Balance,
+    }
+
+    #[derive(Debug, Clone, PartialEq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Quote {
+        pub in_amount: Balance,
+        pub refund_amount: Balance,
+        pub out_amount: Balance,
+        pub out_bonus: Balance,
+    }
+
+    // Tiers are filled in order from the start of the sale, anything after uses out_unit
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct PriceTier {
+        pub in_amount: Balance,
+        pub out_unit: Balance,
+    }
+
+    // Basis points added to the out amount, where the volume bonus stacks with the phase bonuses
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, Copy, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct BonusSchedule {
+        pub whitelist_basis_points: u16,
+        // Applies for early_duration ms from the start of the public phase
+        pub early_basis_points: u16,
+        pub early_duration: Timestamp,
+        // Applies to buys of at least volume_in_threshold
+        pub volume_basis_points: u16,
+        pub volume_in_threshold: Balance,
+    }
+
+    // Mirrors the vesting fields of Recipient on the airdrop contract
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, Copy, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct VestingTerms {
+        pub collectable_at_tge_percentage: u8,
+        pub cliff_duration: Timestamp,
+        pub vesting_duration: Timestamp,
+    }
+
+    #[derive(Debug, Clone, PartialEq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct StatusSummary {
+        pub status: Status,
+        pub paused: bool,
+        // When status will next change on its own, None once Ended or Finalized
+        pub next_status_at: Option<Timestamp>,
+        // In amount still available in the current phase across all buyers
+        pub in_remaining: Balance,
+    }
+
+    #[derive(Debug, Clone, PartialEq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct WhitelistUpdateSummary {
+        pub changed: Vec<AccountId>,
+        // Already in the requested state
+        pub skipped: Vec<AccountId>,
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Referrer {
+        pub in_referred: Balance,
+        pub buyers_referred: u32,
+        pub in_commission: Balance,
+        pub out_bonus: Balance,
+    }
+
+    // Leaf is the blake2x256 hash of the scale encoded (address, allocation)
+    #[derive(Debug, Clone, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct WhitelistProof {
+        pub allocation: Option<Balance>,
+        pub proof: Vec<[u8; 32]>,
     }
 
     #[derive(Debug, Clone, scale::Encode, scale::Decode)]
@@ -56,10 +415,34 @@ mod az_token_sale_to_airdrop {
         pub vesting_duration: Timestamp,
     }
 
+    struct Purchase {
+        quote: Quote,
+        buyer: Buyer,
+        whitelist_phase: bool,
+    }
+
     // === CONTRACT ===
     #[ink(storage)]
     pub struct AzTokenSaleToAirdrop {
         admin: AccountId,
+        pending_admin: Option<AccountId>,
+        operator: Option<AccountId>,
+        treasury: AccountId,
+        payees: Vec<(AccountId, u16)>,
+        payee_totals: Mapping<PayeeAsset, Balance>,
+        hold_proceeds: bool,
+        withdrawable: Mapping<PayeeAsset, Balance>,
+        paused: bool,
+        pause_reason: Option<String>,
+        finalized_at: Option<Timestamp>,
+        final_in_raised: Option<Balance>,
+        vesting_terms: Option<VestingTerms>,
+        whitelist_vesting_terms: Option<VestingTerms>,
+        referral_bonus: Option<ReferralBonus>,
+        referrers: Mapping<AccountId, Referrer>,
+        offchain_in_raised: Balance,
+        offchain_references: Mapping<[u8; 32], AccountId>,
+        bonus_schedule: Option<BonusSchedule>,
         airdrop_smart_contract: AccountId,
         in_unit: Balance,
         out_unit: Balance,
@@ -69,8 +452,24 @@ mod az_token_sale_to_airdrop {
         whitelist_duration: Timestamp,
         in_target: Balance,
         in_raised: Balance,
+        announced_status: Status,
+        min_in_per_buyer: Balance,
+        max_in_per_buyer: Option<Balance>,
+        whitelist_in_target: Option<Balance>,
+        whitelist_in_raised: Balance,
+        in_soft_cap: Option<Balance>,
+        in_refunded: Balance,
+        in_withdrawn: Balance,
+        whitelist_merkle_root: Option<[u8; 32]>,
+        price_tiers: Vec<PriceTier>,
+        in_token: Option<AccountId>,
+        payment_assets: Mapping<AccountId, PaymentAsset>,
+        payment_asset_tokens: Vec<AccountId>,
+        out_unit_end: Option<Balance>,
+        price_step_duration: Timestamp,
     }
     impl AzTokenSaleToAirdrop {
+        #[allow(clippy::too_many_arguments)]
         #[ink(constructor)]
         pub fn new(
             airdrop_smart_contract: AccountId,
@@ -80,12 +479,19 @@ mod az_token_sale_to_airdrop {
             end: Timestamp,
             whitelist_duration: Timestamp,
             in_target: Balance,
+            min_in_per_buyer: Balance,
+            max_in_per_buyer: Option<Balance>,
+            whitelist_in_target: Option<Balance>,
+            in_soft_cap: Option<Balance>,
+            price_tiers: Vec<PriceTier>,
+            out_unit_end: Option<Balance>,
+            price_step_duration: Timestamp,
+            in_token: Option<AccountId>,
+            treasury: AccountId,
+            payees: Vec<(AccountId, u16)>,
+            hold_proceeds: bool,
         ) -> Result<Self> {
-            if start + whitelist_duration >= end {
-                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "Start + whitelist_duration must be less than end".to_string(),
-                ));
-            }
+            Self::validate_schedule(start, whitelist_duration, end, price_step_duration)?;
             if in_unit == 0 || out_unit == 0 || in_target == 0 {
                 return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
                     "In unit, out unit and in target must be positive".to_string(),
@@ -96,9 +502,116 @@ mod az_token_sale_to_airdrop {
                     "In target must be a multiple of in unit".to_string(),
                 ));
             }
+            if min_in_per_buyer % in_unit > 0 || min_in_per_buyer > in_target {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Min in per buyer must be a multiple of in unit and not exceed in target"
+                        .to_string(),
+                ));
+            }
+            if let Some(max_in_per_buyer_unwrapped) = max_in_per_buyer {
+                if max_in_per_buyer_unwrapped == 0
+                    || max_in_per_buyer_unwrapped % in_unit > 0
+                    || max_in_per_buyer_unwrapped < min_in_per_buyer
+                {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Max in per buyer must be a positive multiple of in unit and at least min in per buyer".to_string(),
+                    ));
+                }
+            }
+            if let Some(whitelist_in_target_unwrapped) = whitelist_in_target {
+                if whitelist_in_target_unwrapped % in_unit > 0
+                    || whitelist_in_target_unwrapped > in_target
+                {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Whitelist in target must be a multiple of in unit and not exceed in target"
+                            .to_string(),
+                    ));
+                }
+            }
+            if let Some(in_soft_cap_unwrapped) = in_soft_cap {
+                if in_soft_cap_unwrapped == 0
+                    || in_soft_cap_unwrapped % in_unit > 0
+                    || in_soft_cap_unwrapped > in_target
+                {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "In soft cap must be a positive multiple of in unit and not exceed in target"
+                            .to_string(),
+                    ));
+                }
+            }
+            let mut price_tiers_in_amount: Balance = 0;
+            for price_tier in price_tiers.iter() {
+                if price_tier.in_amount == 0
+                    || price_tier.in_amount % in_unit > 0
+                    || price_tier.out_unit == 0
+                {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Price tier in amount must be a positive multiple of in unit and out unit must be positive".to_string(),
+                    ));
+                }
+                price_tiers_in_amount += price_tier.in_amount;
+            }
+            if price_tiers_in_amount > in_target {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Price tiers must not exceed in target".to_string(),
+                ));
+            }
+            if let Some(out_unit_end_unwrapped) = out_unit_end {
+                if out_unit_end_unwrapped == 0 {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Out unit end must be positive".to_string(),
+                    ));
+                }
+                if !price_tiers.is_empty() {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Price tiers and price schedule can not be combined".to_string(),
+                    ));
+                }
+            } else if price_step_duration > 0 {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Price step duration requires out unit end".to_string(),
+                ));
+            }
+            // payees are optional, the treasury receives everything when there are none
+            if !payees.is_empty() {
+                let mut basis_points_total: u16 = 0;
+                for (index, (payee, basis_points)) in payees.iter().enumerate() {
+                    if *basis_points == 0
+                        || payees[..index].iter().any(|(address, _)| address == payee)
+                    {
+                        return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                            "Payees must be unique with positive basis points".to_string(),
+                        ));
+                    }
+                    basis_points_total = basis_points_total.saturating_add(*basis_points);
+                }
+                if basis_points_total != BASIS_POINTS_TOTAL {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Payees basis points must sum to 10,000".to_string(),
+                    ));
+                }
+            }
 
             Ok(Self {
                 admin: Self::env().caller(),
+                pending_admin: None,
+                operator: None,
+                treasury,
+                payees,
+                payee_totals: Mapping::default(),
+                hold_proceeds,
+                withdrawable: Mapping::default(),
+                paused: false,
+                pause_reason: None,
+                finalized_at: None,
+                final_in_raised: None,
+                vesting_terms: None,
+                whitelist_vesting_terms: None,
+                referral_bonus: None,
+                referrers: Mapping::default(),
+                offchain_in_raised: 0,
+                offchain_references: Mapping::default(),
+                bonus_schedule: None,
                 airdrop_smart_contract,
                 in_unit,
                 out_unit,
@@ -108,14 +621,43 @@ mod az_token_sale_to_airdrop {
                 whitelist_duration,
                 in_target,
                 in_raised: 0,
+                announced_status: Status::Pending,
+                min_in_per_buyer,
+                max_in_per_buyer,
+                whitelist_in_target,
+                whitelist_in_raised: 0,
+                in_soft_cap,
+                in_refunded: 0,
+                in_withdrawn: 0,
+                whitelist_merkle_root: None,
+                price_tiers,
+                out_unit_end,
+                price_step_duration,
+                in_token,
+                payment_assets: Mapping::default(),
+                payment_asset_tokens: Vec::new(),
             })
         }
 
         // === QUERIES ===
         #[ink(message)]
         pub fn config(&self) -> Config {
+            let (price_tier_index, price_tier_in_remaining) = self.current_price_tier();
             Config {
                 admin: self.admin,
+                pending_admin: self.pending_admin,
+                operator: self.operator,
+                treasury: self.treasury,
+                payees: self.payees.clone(),
+                hold_proceeds: self.hold_proceeds,
+                paused: self.paused,
+                pause_reason: self.pause_reason.clone(),
+                finalized_at: self.finalized_at,
+                final_in_raised: self.final_in_raised,
+                vesting_terms: self.vesting_terms,
+                whitelist_vesting_terms: self.whitelist_vesting_terms,
+                referral_bonus: self.referral_bonus,
+                bonus_schedule: self.bonus_schedule,
                 airdrop_smart_contract: self.airdrop_smart_contract,
                 in_unit: self.in_unit,
                 out_unit: self.out_unit,
@@ -124,376 +666,3742 @@ mod az_token_sale_to_airdrop {
                 whitelist_duration: self.whitelist_duration,
                 in_target: self.in_target,
                 in_raised: self.in_raised,
+                min_in_per_buyer: self.min_in_per_buyer,
+                max_in_per_buyer: self.max_in_per_buyer,
+                whitelist_in_target: self.whitelist_in_target,
+                whitelist_in_raised: self.whitelist_in_raised,
+                public_in_raised: self.in_raised - self.whitelist_in_raised,
+                offchain_in_raised: self.offchain_in_raised,
+                in_soft_cap: self.in_soft_cap,
+                in_refunded: self.in_refunded,
+                in_withdrawn: self.in_withdrawn,
+                whitelist_merkle_root: self.whitelist_merkle_root,
+                in_token: self.in_token,
+                payment_assets: self
+                    .payment_asset_tokens
+                    .iter()
+                    .map(|token| (*token, self.payment_assets.get(token).unwrap()))
+                    .collect(),
+                price_tiers: self.price_tiers.clone(),
+                price_tier_index,
+                price_tier_in_remaining,
+                out_unit_end: self.out_unit_end,
+                price_step_duration: self.price_step_duration,
+                current_out_unit: self.current_out_unit(Self::env().block_timestamp()),
             }
         }
 
+        // Beneficiary of the off-chain purchase recorded with reference
+        #[ink(message)]
+        pub fn offchain_reference(&self, reference: [u8; 32]) -> Option<AccountId> {
+            self.offchain_references.get(reference)
+        }
+
+        // Total allocated to payee in asset, where None is AZERO
+        #[ink(message)]
+        pub fn payee_total(&self, payee: AccountId, asset: Option<AccountId>) -> Balance {
+            self.payee_totals.get((payee, asset)).unwrap_or(0)
+        }
+
+        // Simulates a buy at the current block, returning the error buy would return
+        #[ink(message)]
+        pub fn quote(
+            &self,
+            buyer: AccountId,
+            in_amount: Balance,
+            whitelist_proof: Option<WhitelistProof>,
+        ) -> Result<Quote> {
+            let Purchase { quote, .. } = self.prepare_buy(
+                buyer,
+                in_amount,
+                whitelist_proof,
+                Self::env().block_timestamp(),
+            )?;
+
+            Ok(quote)
+        }
+
+        #[ink(message)]
+        pub fn referrer(&self, address: AccountId) -> Referrer {
+            self.referrers.get(address).unwrap_or_default()
+        }
+
         #[ink(message)]
         pub fn show(&self, address: AccountId) -> Buyer {
             self.buyers.get(address).unwrap_or(Buyer {
                 total_in: 0,
+                total_out: 0,
                 whitelisted: false,
+                whitelist_allocation: None,
+                payment_assets_in: Vec::new(),
+                payers_in: Vec::new(),
+                referrer: None,
+                referred_in: 0,
+                referrer_in_commission: 0,
+                referrer_out_bonus: 0,
+                offchain_in: 0,
             })
         }
 
-        // === HANDLES ===
-        #[ink(message, payable)]
-        pub fn buy(&mut self) -> Result<(Balance, Balance)> {
+        #[ink(message)]
+        pub fn status(&self) -> StatusSummary {
             let block_timestamp: Timestamp = Self::env().block_timestamp();
-            // validate sale has started
-            if block_timestamp < self.start {
-                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "Sale has not started".to_string(),
-                ));
-            }
-            // validate sale has not ended
-            if block_timestamp > self.end {
-                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "Sale has ended".to_string(),
-                ));
-            }
-            // validate user is on whitelist if during whitelist duration
-            let caller: AccountId = Self::env().caller();
-            let mut buyer: Buyer = self.show(caller);
-            if self.whitelist_duration > 0
-                && block_timestamp < (self.start + self.whitelist_duration)
-            {
-                if !buyer.whitelisted {
-                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                        "Sale is currently only available to whitelisted addresses".to_string(),
-                    ));
+            let status: Status = self.status_at(block_timestamp);
+            let next_status_at: Option<Timestamp> = match status {
+                Status::Pending => Some(self.start),
+                Status::Whitelist => Some(self.start + self.whitelist_duration),
+                Status::Public | Status::SoldOut => Some(self.end + 1),
+                Status::Ended | Status::Finalized => None,
+            };
+            let mut in_remaining: Balance = match status {
+                Status::Ended | Status::Finalized => 0,
+                _ => self.in_target - self.in_raised,
+            };
+            if status == Status::Whitelist {
+                if let Some(whitelist_in_target) = self.whitelist_in_target {
+                    in_remaining = in_remaining
+                        .min(whitelist_in_target.saturating_sub(self.whitelist_in_raised));
                 }
             }
-            // validate in amount is in units of in_unit
-            let mut in_amount: Balance = self.env().transferred_value();
-            if in_amount == 0 || in_amount % self.in_unit > 0 {
-                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "In amount must be in multiples of in_unit".to_string(),
-                ));
-            }
-            // validate sold out
-            if self.in_raised == self.in_target {
-                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "Sold out".to_string(),
-                ));
-            }
-            let max_in_amount: Balance = self.in_target - self.in_raised;
-            if in_amount > max_in_amount {
-                let refund_amount: Balance = in_amount - max_in_amount;
-                self.transfer_azero(caller, refund_amount)?;
-                in_amount = max_in_amount
+
+            StatusSummary {
+                status,
+                paused: self.paused,
+                next_status_at,
+                in_remaining,
             }
-            let out_amount: Balance = (U256::from(in_amount) * U256::from(self.out_unit)
-                / U256::from(self.in_unit))
-            .as_u128();
-            let description: Option<String> = None;
-            // Add amount to airdrop contract
-            build_call::<super::az_token_sale_to_airdrop::Environment>()
-                .call_type(Call::new(self.airdrop_smart_contract))
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!("recipient_add")))
-                        .push_arg(caller)
-                        .push_arg(out_amount)
-                        .push_arg(description),
-                )
-                .call_flags(CallFlags::default())
-                .returns::<core::result::Result<Recipient, AzTokenSaleToAirdropError>>()
-                .invoke()?;
-            // Send AZERO to admin
-            self.transfer_azero(self.admin, in_amount)?;
-            self.in_raised += in_amount;
-            buyer.total_in += in_amount;
-            self.buyers.insert(caller, &buyer);
+        }
 
-            Ok((in_amount, out_amount))
+        // Held proceeds payee can withdraw in asset, where None is AZERO
+        #[ink(message)]
+        pub fn withdrawable(&self, payee: AccountId, asset: Option<AccountId>) -> Balance {
+            self.withdrawable.get((payee, asset)).unwrap_or(0)
         }
 
+        // === HANDLES ===
         #[ink(message)]
-        pub fn whitelist_add(&mut self, address: AccountId) -> Result<Buyer> {
+        pub fn admin_transfer_accept(&mut self) -> Result<AccountId> {
             let caller: AccountId = Self::env().caller();
-            Self::authorise(caller, self.admin)?;
+            let pending_admin: AccountId = self
+                .pending_admin
+                .ok_or(AzTokenSaleToAirdropError::Unauthorised)?;
+            Self::authorise(caller, pending_admin)?;
 
-            let mut buyer: Buyer = self.show(address);
-            if buyer.whitelisted {
-                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "Already on whitelist".to_string(),
-                ));
-            } else {
-                buyer.whitelisted = true;
-                self.buyers.insert(address, &buyer);
-            }
+            let previous_admin: AccountId = self.admin;
+            self.admin = pending_admin;
+            self.pending_admin = None;
 
-            Ok(buyer)
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::AdminTransferAccept(AdminTransferAccept {
+                    previous_admin,
+                    admin: self.admin,
+                }),
+            );
+
+            Ok(self.admin)
         }
 
         #[ink(message)]
-        pub fn whitelist_remove(&mut self, address: AccountId) -> Result<Buyer> {
+        pub fn admin_transfer_cancel(&mut self) -> Result<()> {
             let caller: AccountId = Self::env().caller();
             Self::authorise(caller, self.admin)?;
+            let pending_admin: AccountId =
+                self.pending_admin
+                    .ok_or(AzTokenSaleToAirdropError::NotFound(
+                        "Pending admin".to_string(),
+                    ))?;
 
-            let mut buyer: Buyer = self.show(address);
-            if buyer.whitelisted {
-                buyer.whitelisted = false;
-                self.buyers.insert(address, &buyer);
-            } else {
+            self.pending_admin = None;
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::AdminTransferCancel(AdminTransferCancel { pending_admin }),
+            );
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn admin_transfer_propose(&mut self, new_admin: AccountId) -> Result<AccountId> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+            if new_admin == self.admin {
                 return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "Not on whitelist".to_string(),
+                    "New admin must be different to admin".to_string(),
                 ));
             }
 
-            Ok(buyer)
-        }
+            self.pending_admin = Some(new_admin);
 
-        // === PRIVATE ===
-        fn authorise(allowed: AccountId, received: AccountId) -> Result<()> {
-            if allowed != received {
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::AdminTransferPropose(AdminTransferPropose {
+                    admin: self.admin,
+                    pending_admin: new_admin,
+                }),
+            );
+
+            Ok(new_admin)
+        }
+
+        #[ink(message)]
+        pub fn bonus_schedule_update(
+            &mut self,
+            bonus_schedule: Option<BonusSchedule>,
+        ) -> Result<()> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+            self.validate_before_start()?;
+            if let Some(bonus_schedule) = bonus_schedule {
+                if [
+                    bonus_schedule.whitelist_basis_points,
+                    bonus_schedule.early_basis_points,
+                    bonus_schedule.volume_basis_points,
+                ]
+                .iter()
+                .any(|basis_points| *basis_points > BASIS_POINTS_TOTAL)
+                {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Bonus must be at most 10,000 basis points".to_string(),
+                    ));
+                }
+            }
+
+            self.bonus_schedule = bonus_schedule;
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::BonusScheduleUpdate(BonusScheduleUpdate { bonus_schedule }),
+            );
+
+            Ok(())
+        }
+
+        #[ink(message, payable)]
+        pub fn buy(
+            &mut self,
+            whitelist_proof: Option<WhitelistProof>,
+        ) -> Result<(Balance, Balance, Balance)> {
+            self.buy_for(Self::env().caller(), whitelist_proof)
+        }
+
+        // Beneficiary's whitelist status and limits apply, while the caller pays and gets any refund,
+        // including a refund claimed when the soft cap is not reached
+        #[ink(message, payable)]
+        pub fn buy_for(
+            &mut self,
+            beneficiary: AccountId,
+            whitelist_proof: Option<WhitelistProof>,
+        ) -> Result<(Balance, Balance, Balance)> {
+            if self.in_token.is_some() {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is denominated in a PSP22 token".to_string(),
+                ));
+            }
+            let block_timestamp: Timestamp = Self::env().block_timestamp();
+            let caller: AccountId = Self::env().caller();
+            let mut purchase: Purchase = self.prepare_buy(
+                beneficiary,
+                self.env().transferred_value(),
+                whitelist_proof,
+                block_timestamp,
+            )?;
+            if purchase.quote.refund_amount > 0 {
+                self.transfer_azero(caller, purchase.quote.refund_amount)?;
+            }
+            self.distribute_proceeds(None, purchase.quote.in_amount)?;
+            if caller != beneficiary {
+                Self::credit_payer(&mut purchase.buyer, caller, purchase.quote.in_amount);
+            }
+
+            self.settle_buy(beneficiary, Some(caller), purchase, None, block_timestamp)
+        }
+
+        // Registers OTC purchases paid for in one payment equal to the sum of the in amounts.
+        // Entries that can not be bought are returned with the reason and refunded to the caller
+        // in full, along with anything over what each beneficiary can buy.
+        #[ink(message, payable)]
+        pub fn buy_for_many(
+            &mut self,
+            purchases: Vec<(AccountId, Balance)>,
+        ) -> Result<Vec<Result<Quote>>> {
+            let caller: AccountId = Self::env().caller();
+            if caller != self.admin && Some(caller) != self.operator {
                 return Err(AzTokenSaleToAirdropError::Unauthorised);
             }
+            if self.in_token.is_some() {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is denominated in a PSP22 token".to_string(),
+                ));
+            }
+            if purchases.is_empty() {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Purchases can not be empty".to_string(),
+                ));
+            }
+            // release builds don't check for overflow, so a wrapped sum would pass as a small payment
+            let in_total: Balance = purchases
+                .iter()
+                .try_fold(0, |total: Balance, (_, in_amount)| {
+                    total.checked_add(*in_amount)
+                })
+                .ok_or(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sum of in amounts overflows".to_string(),
+                ))?;
+            if in_total != self.env().transferred_value() {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Payment must equal the sum of in amounts".to_string(),
+                ));
+            }
+
+            let block_timestamp: Timestamp = Self::env().block_timestamp();
+            self.validate_on_sale(block_timestamp)?;
+            let mut results: Vec<Result<Quote>> = Vec::new();
+            let mut in_accepted: Balance = 0;
+            for (beneficiary, in_amount) in purchases {
+                // e.g. sold out, below the minimum or maximum per buyer reached
+                let mut purchase: Purchase =
+                    match self.prepare_buy(beneficiary, in_amount, None, block_timestamp) {
+                        Ok(purchase) => purchase,
+                        Err(error) => {
+                            results.push(Err(error));
+                            continue;
+                        }
+                    };
+                in_accepted += purchase.quote.in_amount;
+                results.push(Ok(purchase.quote.clone()));
+                if caller != beneficiary {
+                    Self::credit_payer(&mut purchase.buyer, caller, purchase.quote.in_amount);
+                }
+                self.settle_buy(beneficiary, Some(caller), purchase, None, block_timestamp)?;
+            }
+            if in_total > in_accepted {
+                self.transfer_azero(caller, in_total - in_accepted)?;
+            }
+            if in_accepted > 0 {
+                self.distribute_proceeds(None, in_accepted)?;
+            }
+
+            Ok(results)
+        }
+
+        // Only the accepted in amount is transferred from the buyer, so nothing needs refunding
+        #[ink(message)]
+        pub fn buy_with_in_token(
+            &mut self,
+            in_amount: Balance,
+            whitelist_proof: Option<WhitelistProof>,
+        ) -> Result<(Balance, Balance, Balance)> {
+            let Some(in_token) = self.in_token else {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is denominated in AZERO".to_string(),
+                ));
+            };
+            let block_timestamp: Timestamp = Self::env().block_timestamp();
+            let caller: AccountId = Self::env().caller();
+            let purchase: Purchase =
+                self.prepare_buy(caller, in_amount, whitelist_proof, block_timestamp)?;
+            self.psp22_transfer_from(
+                in_token,
+                caller,
+                Self::env().account_id(),
+                purchase.quote.in_amount,
+            )?;
+            self.distribute_proceeds(Some(in_token), purchase.quote.in_amount)?;
+
+            self.settle_buy(caller, Some(caller), purchase, None, block_timestamp)
+        }
+
+        // Returns the amount of the payment asset accepted, the out amount and the out bonus
+        #[ink(message)]
+        pub fn buy_with_payment_asset(
+            &mut self,
+            token: AccountId,
+            in_amount: Balance,
+            whitelist_proof: Option<WhitelistProof>,
+        ) -> Result<(Balance, Balance, Balance)> {
+            let mut payment_asset: PaymentAsset =
+                self.payment_assets
+                    .get(token)
+                    .ok_or(AzTokenSaleToAirdropError::NotFound(
+                        "Payment asset".to_string(),
+                    ))?;
+            if in_amount == 0 || in_amount % payment_asset.in_unit > 0 {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In amount must be in multiples of in_unit".to_string(),
+                ));
+            }
+            let mut asset_in_amount: Balance = in_amount;
+            if let Some(in_target) = payment_asset.in_target {
+                if payment_asset.in_raised >= in_target {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Payment asset sold out".to_string(),
+                    ));
+                }
+                asset_in_amount = asset_in_amount.min(in_target - payment_asset.in_raised);
+            }
+            let block_timestamp: Timestamp = Self::env().block_timestamp();
+            let caller: AccountId = Self::env().caller();
+            let mut purchase: Purchase = self.prepare_buy(
+                caller,
+                asset_in_amount / payment_asset.in_unit * self.in_unit,
+                whitelist_proof,
+                block_timestamp,
+            )?;
+            // every limit the in amount can be clamped to is a multiple of in_unit
+            debug_assert_eq!(purchase.quote.in_amount % self.in_unit, 0);
+            let accepted_amount: Balance =
+                purchase.quote.in_amount / self.in_unit * payment_asset.in_unit;
+            self.psp22_transfer_from(token, caller, Self::env().account_id(), accepted_amount)?;
+            self.distribute_proceeds(Some(token), accepted_amount)?;
+            payment_asset.in_raised += accepted_amount;
+            self.payment_assets.insert(token, &payment_asset);
+            match purchase
+                .buyer
+                .payment_assets_in
+                .iter_mut()
+                .find(|(buyer_token, _)| *buyer_token == token)
+            {
+                Some((_, total)) => *total += accepted_amount,
+                None => purchase
+                    .buyer
+                    .payment_assets_in
+                    .push((token, accepted_amount)),
+            }
+
+            let (_, out_amount, out_bonus) =
+                self.settle_buy(caller, Some(caller), purchase, Some(token), block_timestamp)?;
+
+            Ok((accepted_amount, out_amount, out_bonus))
+        }
+
+        #[ink(message, payable)]
+        pub fn buy_with_referrer(
+            &mut self,
+            referrer: AccountId,
+            whitelist_proof: Option<WhitelistProof>,
+        ) -> Result<(Balance, Balance, Balance)> {
+            if self.in_token.is_some() {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is denominated in a PSP22 token".to_string(),
+                ));
+            }
+            let block_timestamp: Timestamp = Self::env().block_timestamp();
+            let caller: AccountId = Self::env().caller();
+            if referrer == caller {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Buyer can not refer themselves".to_string(),
+                ));
+            }
+            let mut purchase: Purchase = self.prepare_buy(
+                caller,
+                self.env().transferred_value(),
+                whitelist_proof,
+                block_timestamp,
+            )?;
+            if purchase
+                .buyer
+                .referrer
+                .is_some_and(|buyer_referrer| buyer_referrer != referrer)
+            {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Buyer already has a different referrer".to_string(),
+                ));
+            }
+            if purchase.quote.refund_amount > 0 {
+                self.transfer_azero(caller, purchase.quote.refund_amount)?;
+            }
+            let (in_commission, out_bonus) =
+                self.referral_bonus_amounts(purchase.quote.in_amount, purchase.quote.out_amount);
+            self.distribute_proceeds(None, purchase.quote.in_amount - in_commission)?;
+
+            // Credit referrer
+            let new_referral: bool = purchase.buyer.referrer.is_none();
+            purchase.buyer.referrer = Some(referrer);
+            purchase.buyer.referred_in += purchase.quote.in_amount;
+            purchase.buyer.referrer_in_commission += in_commission;
+            purchase.buyer.referrer_out_bonus += out_bonus;
+            if in_commission > 0 {
+                self.pay(None, referrer, in_commission)?;
+            }
+            if out_bonus > 0 {
+                self.airdrop_recipient_add(referrer, out_bonus)?;
+            }
+            let mut referrer_totals: Referrer = self.referrer(referrer);
+            referrer_totals.in_referred += purchase.quote.in_amount;
+            if new_referral {
+                referrer_totals.buyers_referred += 1;
+            }
+            referrer_totals.in_commission += in_commission;
+            referrer_totals.out_bonus += out_bonus;
+            self.referrers.insert(referrer, &referrer_totals);
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::Referral(Referral {
+                    referrer,
+                    buyer: caller,
+                    in_amount: purchase.quote.in_amount,
+                    in_commission,
+                    out_bonus,
+                }),
+            );
+
+            self.settle_buy(caller, Some(caller), purchase, None, block_timestamp)
+        }
+
+        #[ink(message)]
+        pub fn claim_refund(&mut self) -> Result<(Balance, Balance)> {
+            let in_soft_cap: Balance = self.soft_cap()?;
+            if Self::env().block_timestamp() <= self.end && self.finalized_at.is_none() {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has not ended".to_string(),
+                ));
+            }
+            if self.in_raised >= in_soft_cap {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Soft cap reached".to_string(),
+                ));
+            }
+            let caller: AccountId = Self::env().caller();
+            let mut buyer: Buyer = self.show(caller);
+            if buyer.total_in == 0 {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Nothing to refund".to_string(),
+                ));
+            }
+            self.announce_status(Self::env().block_timestamp());
+
+            // Off-chain amounts are refunded off-chain, but the whole allocation is removed
+            let in_amount: Balance = buyer.total_in - buyer.offchain_in;
+            let out_amount: Balance = buyer.total_out;
+            let payment_assets_in: Vec<(AccountId, Balance)> = buyer.payment_assets_in;
+            let payers_in: Vec<(AccountId, Balance)> = buyer.payers_in;
+            let mut base_in_amount: Balance = in_amount;
+            buyer.total_in = 0;
+            buyer.total_out = 0;
+            buyer.payment_assets_in = Vec::new();
+            buyer.payers_in = Vec::new();
+            buyer.offchain_in = 0;
+            let referral: Option<(AccountId, Balance)> = self.unwind_referral(&mut buyer);
+            self.buyers.insert(caller, &buyer);
+            self.in_refunded += in_amount;
+            // Remove amount from airdrop contract
+            self.airdrop_recipient_subtract(caller, out_amount)?;
+            if let Some((referrer, out_bonus)) = referral {
+                if out_bonus > 0 {
+                    self.airdrop_recipient_subtract(referrer, out_bonus)?;
+                }
+            }
+            for (token, amount) in payment_assets_in {
+                let payment_asset: PaymentAsset = self.payment_assets.get(token).unwrap();
+                base_in_amount -= amount / payment_asset.in_unit * self.in_unit;
+                self.psp22_transfer(token, caller, amount)?;
+            }
+            // what was paid on the buyer's behalf goes back to whoever paid it
+            for (payer, amount) in payers_in {
+                base_in_amount -= amount;
+                self.transfer_asset(self.in_token, payer, amount)?;
+            }
+            if base_in_amount > 0 {
+                self.transfer_asset(self.in_token, caller, base_in_amount)?;
+            }
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::Refund(Refund {
+                    buyer: caller,
+                    in_amount,
+                    out_amount,
+                }),
+            );
+
+            Ok((in_amount, out_amount))
+        }
+
+        #[ink(message)]
+        pub fn withdraw(
+            &mut self,
+            asset: Option<AccountId>,
+            amount: Balance,
+            to: AccountId,
+        ) -> Result<Balance> {
+            let caller: AccountId = Self::env().caller();
+            let withdrawable: Balance = self.withdrawable(caller, asset);
+            if withdrawable == 0 {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Nothing to withdraw".to_string(),
+                ));
+            }
+            if amount == 0 || amount > withdrawable {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Amount must be positive and at most withdrawable".to_string(),
+                ));
+            }
+            if let Some(in_soft_cap) = self.in_soft_cap {
+                if self.in_raised < in_soft_cap {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Soft cap not reached".to_string(),
+                    ));
+                }
+            }
+            self.announce_status(Self::env().block_timestamp());
+
+            self.withdrawable
+                .insert((caller, asset), &(withdrawable - amount));
+            match asset {
+                Some(token) if asset != self.in_token => {
+                    let mut payment_asset: PaymentAsset = self.payment_assets.get(token).unwrap();
+                    payment_asset.in_withdrawn += amount;
+                    self.payment_assets.insert(token, &payment_asset);
+                }
+                _ => self.in_withdrawn += amount,
+            }
+            self.transfer_asset(asset, to, amount)?;
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::Withdraw(Withdraw {
+                    to,
+                    payment_asset: asset,
+                    amount,
+                }),
+            );
+
+            Ok(amount)
+        }
+
+        // Only allowed before the sale starts, so buyers never see the sale close again
+        #[ink(message)]
+        pub fn delay_start(&mut self, new_start: Timestamp) -> Result<()> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+            self.validate_not_finalized()?;
+            self.validate_before_start()?;
+            if new_start <= self.start {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "New start must be after start".to_string(),
+                ));
+            }
+            Self::validate_schedule(
+                new_start,
+                self.whitelist_duration,
+                self.end,
+                self.price_step_duration,
+            )?;
+
+            self.start = new_start;
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::ScheduleUpdate(ScheduleUpdate {
+                    start: self.start,
+                    end: self.end,
+                }),
+            );
 
             Ok(())
         }
 
-        fn transfer_azero(&self, address: AccountId, amount: Balance) -> Result<()> {
-            if self.env().transfer(address, amount).is_err() {
+        // Only allowed before the sale ends, so refunds can not be reopened to buying.
+        // A price schedule is interpolated over start to end, so it locks the end once the sale starts.
+        #[ink(message)]
+        pub fn extend_end(&mut self, new_end: Timestamp) -> Result<()> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+            self.validate_not_finalized()?;
+            if Self::env().block_timestamp() > self.end {
                 return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "Insufficient AZERO balance".to_string(),
+                    "Sale has ended".to_string(),
+                ));
+            }
+            if new_end <= self.end {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "New end must be after end".to_string(),
+                ));
+            }
+            if self.out_unit_end.is_some() && Self::env().block_timestamp() >= self.start {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Price schedule can not be extended after start".to_string(),
+                ));
+            }
+            Self::validate_schedule(
+                self.start,
+                self.whitelist_duration,
+                new_end,
+                self.price_step_duration,
+            )?;
+
+            self.end = new_end;
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::ScheduleUpdate(ScheduleUpdate {
+                    start: self.start,
+                    end: self.end,
+                }),
+            );
+
+            Ok(())
+        }
+
+        // Closes the sale for good, e.g. to stop it early
+        #[ink(message)]
+        pub fn finalize(&mut self) -> Result<Balance> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+            self.validate_not_finalized()?;
+
+            let block_timestamp: Timestamp = Self::env().block_timestamp();
+            // announce any transition before finalizing, e.g. Ended when finalized after end
+            self.announce_status(block_timestamp);
+            self.finalized_at = Some(block_timestamp);
+            self.final_in_raised = Some(self.in_raised);
+            self.announce_status(block_timestamp);
+
+            Ok(self.in_raised)
+        }
+
+        // Operator can register OTC purchases alongside the admin
+        #[ink(message)]
+        pub fn operator_update(&mut self, operator: Option<AccountId>) -> Result<()> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+
+            self.operator = operator;
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::OperatorUpdate(OperatorUpdate { operator }),
+            );
+
+            Ok(())
+        }
+
+        // Blocks buying until unpaused, leaving queries and refunds available
+        #[ink(message)]
+        pub fn pause(&mut self, reason: Option<String>) -> Result<()> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+            if self.paused {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is already paused".to_string(),
+                ));
+            }
+
+            self.paused = true;
+            self.pause_reason = reason.clone();
+
+            // emit event
+            Self::emit_event(self.env(), Event::Pause(Pause { reason }));
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn payment_asset_add(
+            &mut self,
+            token: AccountId,
+            in_unit: Balance,
+            in_target: Option<Balance>,
+        ) -> Result<PaymentAsset> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+            self.validate_before_start()?;
+            if self.payment_assets.contains(token) || self.in_token == Some(token) {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Payment asset already accepted".to_string(),
+                ));
+            }
+            if in_unit == 0 {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In unit must be positive".to_string(),
+                ));
+            }
+            if let Some(in_target_unwrapped) = in_target {
+                if in_target_unwrapped == 0 || in_target_unwrapped % in_unit > 0 {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "In target must be a positive multiple of in unit".to_string(),
+                    ));
+                }
+            }
+
+            let payment_asset = PaymentAsset {
+                in_unit,
+                in_target,
+                in_raised: 0,
+                in_withdrawn: 0,
+            };
+            self.payment_assets.insert(token, &payment_asset);
+            self.payment_asset_tokens.push(token);
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::PaymentAssetAdd(PaymentAssetAdd {
+                    token,
+                    in_unit,
+                    in_target,
+                }),
+            );
+
+            Ok(payment_asset)
+        }
+
+        #[ink(message)]
+        pub fn payment_asset_remove(&mut self, token: AccountId) -> Result<()> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+            self.validate_before_start()?;
+            if !self.payment_assets.contains(token) {
+                return Err(AzTokenSaleToAirdropError::NotFound(
+                    "Payment asset".to_string(),
                 ));
             }
 
+            self.payment_assets.remove(token);
+            self.payment_asset_tokens
+                .retain(|payment_asset_token| *payment_asset_token != token);
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::PaymentAssetRemove(PaymentAssetRemove { token }),
+            );
+
             Ok(())
         }
-    }
-
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-        use ink::env::{
-            test::{default_accounts, set_caller, DefaultAccounts},
-            DefaultEnvironment,
-        };
-
-        const MOCK_IN_UNIT: Balance = 10;
-        const MOCK_OUT_UNIT: Balance = 500;
-        const MOCK_START: Timestamp = 654_654;
-        const MOCK_END: Timestamp = 754_654;
-        const MOCK_WHITELIST_DURATION: Timestamp = 1_000;
-        const MOCK_IN_TARGET: Balance = 50_000_000_000_000_000;
 
-        // === HELPERS ===
-        fn init() -> (DefaultAccounts<DefaultEnvironment>, AzTokenSaleToAirdrop) {
-            let accounts = default_accounts();
+        // Counts toward in_raised with the same pricing and limits as buy, without any payment
+        #[ink(message)]
+        pub fn record_offchain_purchase(
+            &mut self,
+            beneficiary: AccountId,
+            in_equivalent: Balance,
+            reference: [u8; 32],
+        ) -> Result<(Balance, Balance, Balance)> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+            if self.offchain_references.contains(reference) {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Reference already recorded".to_string(),
+                ));
+            }
+            let block_timestamp: Timestamp = Self::env().block_timestamp();
+            let mut purchase: Purchase =
+                self.prepare_buy(beneficiary, in_equivalent, None, block_timestamp)?;
+            if purchase.quote.refund_amount > 0 {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In equivalent exceeds what beneficiary can buy".to_string(),
+                ));
+            }
+
+            self.offchain_references.insert(reference, &beneficiary);
+            self.offchain_in_raised += in_equivalent;
+            purchase.buyer.offchain_in += in_equivalent;
+            let out_amount: Balance = purchase.quote.out_amount + purchase.quote.out_bonus;
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::OffchainPurchase(OffchainPurchase {
+                    beneficiary,
+                    in_equivalent,
+                    out_amount,
+                    reference,
+                }),
+            );
+
+            self.settle_buy(beneficiary, None, purchase, None, block_timestamp)
+        }
+
+        #[ink(message)]
+        pub fn referral_bonus_update(
+            &mut self,
+            referral_bonus: Option<ReferralBonus>,
+        ) -> Result<()> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+            self.validate_before_start()?;
+            if let Some(ReferralBonus::Out(basis_points) | ReferralBonus::In(basis_points)) =
+                referral_bonus
+            {
+                if basis_points == 0 || basis_points > BASIS_POINTS_TOTAL {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Referral bonus must be between 1 and 10,000 basis points".to_string(),
+                    ));
+                }
+            }
+
+            self.referral_bonus = referral_bonus;
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::ReferralBonusUpdate(ReferralBonusUpdate { referral_bonus }),
+            );
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<()> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+            if !self.paused {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is not paused".to_string(),
+                ));
+            }
+
+            self.paused = false;
+            self.pause_reason = None;
+
+            // emit event
+            Self::emit_event(self.env(), Event::Unpause(Unpause {}));
+
+            Ok(())
+        }
+
+        // Without vesting terms, recipients get the airdrop contract's defaults.
+        // Terms are only applied to new recipients, see settle_buy.
+        #[ink(message)]
+        pub fn vesting_terms_update(
+            &mut self,
+            vesting_terms: Option<VestingTerms>,
+            whitelist_vesting_terms: Option<VestingTerms>,
+        ) -> Result<()> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+            self.validate_before_start()?;
+            if [vesting_terms, whitelist_vesting_terms]
+                .iter()
+                .flatten()
+                .any(|terms| terms.collectable_at_tge_percentage > 100)
+            {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Collectable at tge percentage must be at most 100".to_string(),
+                ));
+            }
+
+            self.vesting_terms = vesting_terms;
+            self.whitelist_vesting_terms = whitelist_vesting_terms;
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::VestingTermsUpdate(VestingTermsUpdate {
+                    vesting_terms,
+                    whitelist_vesting_terms,
+                }),
+            );
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn whitelist_add(&mut self, address: AccountId) -> Result<Buyer> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+
+            let buyer: Buyer = self.whitelist_set(address, true).ok_or(
+                AzTokenSaleToAirdropError::UnprocessableEntity("Already on whitelist".to_string()),
+            )?;
+            self.announce_status(Self::env().block_timestamp());
+
+            Ok(buyer)
+        }
+
+        #[ink(message)]
+        pub fn whitelist_add_many(
+            &mut self,
+            addresses: Vec<AccountId>,
+        ) -> Result<WhitelistUpdateSummary> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+
+            let summary: WhitelistUpdateSummary = self.whitelist_set_many(addresses, true);
+            self.announce_status(Self::env().block_timestamp());
+
+            Ok(summary)
+        }
+
+        #[ink(message)]
+        pub fn whitelist_merkle_root_update(
+            &mut self,
+            whitelist_merkle_root: Option<[u8; 32]>,
+        ) -> Result<Option<[u8; 32]>> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+
+            self.whitelist_merkle_root = whitelist_merkle_root;
+
+            // emit event
+            Self::emit_event(
+                self.env(),
+                Event::WhitelistMerkleRootUpdate(WhitelistMerkleRootUpdate {
+                    whitelist_merkle_root,
+                }),
+            );
+
+            Ok(self.whitelist_merkle_root)
+        }
+
+        #[ink(message)]
+        pub fn whitelist_remove(&mut self, address: AccountId) -> Result<Buyer> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+
+            let buyer: Buyer = self.whitelist_set(address, false).ok_or(
+                AzTokenSaleToAirdropError::UnprocessableEntity("Not on whitelist".to_string()),
+            )?;
+            self.announce_status(Self::env().block_timestamp());
+
+            Ok(buyer)
+        }
+
+        #[ink(message)]
+        pub fn whitelist_remove_many(
+            &mut self,
+            addresses: Vec<AccountId>,
+        ) -> Result<WhitelistUpdateSummary> {
+            let caller: AccountId = Self::env().caller();
+            Self::authorise(caller, self.admin)?;
+
+            let summary: WhitelistUpdateSummary = self.whitelist_set_many(addresses, false);
+            self.announce_status(Self::env().block_timestamp());
+
+            Ok(summary)
+        }
+
+        // === PRIVATE ===
+        fn airdrop_recipient_add(&self, address: AccountId, amount: Balance) -> Result<Recipient> {
+            let description: Option<String> = None;
+            build_call::<super::az_token_sale_to_airdrop::Environment>()
+                .call_type(Call::new(self.airdrop_smart_contract))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("recipient_add")))
+                        .push_arg(address)
+                        .push_arg(amount)
+                        .push_arg(description),
+                )
+                .call_flags(CallFlags::default())
+                .returns::<core::result::Result<Recipient, AzTokenSaleToAirdropError>>()
+                .invoke()
+        }
+
+        fn airdrop_recipient_subtract(
+            &self,
+            address: AccountId,
+            amount: Balance,
+        ) -> Result<Recipient> {
+            let description: Option<String> = None;
+            build_call::<super::az_token_sale_to_airdrop::Environment>()
+                .call_type(Call::new(self.airdrop_smart_contract))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("recipient_subtract")))
+                        .push_arg(address)
+                        .push_arg(amount)
+                        .push_arg(description),
+                )
+                .call_flags(CallFlags::default())
+                .returns::<core::result::Result<Recipient, AzTokenSaleToAirdropError>>()
+                .invoke()
+        }
+
+        // Called by this contract as a sub admin of the airdrop contract, like recipient_add
+        fn airdrop_recipient_vesting_update(
+            &self,
+            address: AccountId,
+            vesting_terms: VestingTerms,
+        ) -> Result<Recipient> {
+            build_call::<super::az_token_sale_to_airdrop::Environment>()
+                .call_type(Call::new(self.airdrop_smart_contract))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                        "recipient_vesting_update"
+                    )))
+                    .push_arg(address)
+                    .push_arg(vesting_terms.collectable_at_tge_percentage)
+                    .push_arg(vesting_terms.cliff_duration)
+                    .push_arg(vesting_terms.vesting_duration),
+                )
+                .call_flags(CallFlags::default())
+                .returns::<core::result::Result<Recipient, AzTokenSaleToAirdropError>>()
+                .invoke()
+        }
+
+        // Lifecycle events are emitted by the first successful call that observes the transition
+        fn announce_status(&mut self, block_timestamp: Timestamp) {
+            let status: Status = self.status_at(block_timestamp);
+            if status == self.announced_status {
+                return;
+            }
+
+            match status {
+                Status::Pending => {}
+                Status::Whitelist => Self::emit_event(
+                    self.env(),
+                    Event::WhitelistPhaseStart(WhitelistPhaseStart {
+                        timestamp: block_timestamp,
+                    }),
+                ),
+                Status::Public => Self::emit_event(
+                    self.env(),
+                    Event::PublicPhaseStart(PublicPhaseStart {
+                        timestamp: block_timestamp,
+                    }),
+                ),
+                Status::SoldOut => Self::emit_event(
+                    self.env(),
+                    Event::SoldOut(SoldOut {
+                        timestamp: block_timestamp,
+                        in_raised: self.in_raised,
+                    }),
+                ),
+                Status::Ended => Self::emit_event(
+                    self.env(),
+                    Event::Ended(Ended {
+                        timestamp: block_timestamp,
+                        in_raised: self.in_raised,
+                    }),
+                ),
+                Status::Finalized => Self::emit_event(
+                    self.env(),
+                    Event::Finalize(Finalize {
+                        timestamp: block_timestamp,
+                        in_raised: self.in_raised,
+                    }),
+                ),
+            }
+            self.announced_status = status;
+        }
+
+        fn authorise(allowed: AccountId, received: AccountId) -> Result<()> {
+            if allowed != received {
+                return Err(AzTokenSaleToAirdropError::Unauthorised);
+            }
+
+            Ok(())
+        }
+
+        fn transfer_azero(&self, address: AccountId, amount: Balance) -> Result<()> {
+            if self.env().transfer(address, amount).is_err() {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Insufficient AZERO balance".to_string(),
+                ));
+            }
+
+            Ok(())
+        }
+
+        // Splits in_amount across the price tiers it crosses, starting from in_raised
+        fn calculate_out_amount(&self, in_amount: Balance, block_timestamp: Timestamp) -> Balance {
+            let mut out_amount: U256 = U256::zero();
+            let mut in_remaining: Balance = in_amount;
+            let mut position: Balance = self.in_raised;
+            let mut tier_end: Balance = 0;
+            for price_tier in self.price_tiers.iter() {
+                tier_end += price_tier.in_amount;
+                if in_remaining > 0 && position < tier_end {
+                    let tier_in_amount: Balance = in_remaining.min(tier_end - position);
+                    out_amount += U256::from(tier_in_amount) * U256::from(price_tier.out_unit)
+                        / U256::from(self.in_unit);
+                    in_remaining -= tier_in_amount;
+                    position += tier_in_amount;
+                }
+            }
+            out_amount += U256::from(in_remaining)
+                * U256::from(self.current_out_unit(block_timestamp))
+                / U256::from(self.in_unit);
+
+            out_amount.as_u128()
+        }
+
+        fn calculate_out_bonus(
+            &self,
+            in_amount: Balance,
+            out_amount: Balance,
+            block_timestamp: Timestamp,
+        ) -> Balance {
+            let Some(bonus_schedule) = self.bonus_schedule else {
+                return 0;
+            };
+            let public_start: Timestamp = self.start + self.whitelist_duration;
+            let mut basis_points: u32 = 0;
+            if block_timestamp < public_start {
+                basis_points += u32::from(bonus_schedule.whitelist_basis_points);
+            } else if block_timestamp < public_start + bonus_schedule.early_duration {
+                basis_points += u32::from(bonus_schedule.early_basis_points);
+            }
+            if in_amount >= bonus_schedule.volume_in_threshold {
+                basis_points += u32::from(bonus_schedule.volume_basis_points);
+            }
+
+            (U256::from(out_amount) * U256::from(basis_points) / U256::from(BASIS_POINTS_TOTAL))
+                .as_u128()
+        }
+
+        fn credit_payer(buyer: &mut Buyer, payer: AccountId, in_amount: Balance) {
+            match buyer
+                .payers_in
+                .iter_mut()
+                .find(|(buyer_payer, _)| *buyer_payer == payer)
+            {
+                Some((_, total)) => *total += in_amount,
+                None => buyer.payers_in.push((payer, in_amount)),
+            }
+        }
+
+        // Interpolates from out_unit at start to out_unit_end at end, per step if set
+        fn current_out_unit(&self, block_timestamp: Timestamp) -> Balance {
+            let Some(out_unit_end) = self.out_unit_end else {
+                return self.out_unit;
+            };
+            let duration: Timestamp = self.end - self.start;
+            let mut elapsed: Timestamp = block_timestamp.clamp(self.start, self.end) - self.start;
+            if self.price_step_duration > 0 {
+                elapsed -= elapsed % self.price_step_duration;
+            }
+            let difference: U256 = U256::from(self.out_unit.abs_diff(out_unit_end))
+                * U256::from(elapsed)
+                / U256::from(duration);
+            if out_unit_end >= self.out_unit {
+                self.out_unit + difference.as_u128()
+            } else {
+                self.out_unit - difference.as_u128()
+            }
+        }
+
+        // Returns the index of the active price tier and the in amount left in it
+        fn current_price_tier(&self) -> (Option<u32>, Balance) {
+            let mut tier_end: Balance = 0;
+            for (index, price_tier) in self.price_tiers.iter().enumerate() {
+                tier_end += price_tier.in_amount;
+                if self.in_raised < tier_end {
+                    return (Some(index as u32), tier_end - self.in_raised);
+                }
+            }
+
+            (None, self.in_target - self.in_raised)
+        }
+
+        // Splits amount by basis points, with any rounding dust going to the first payee
+        fn distribute_proceeds(
+            &mut self,
+            asset: Option<AccountId>,
+            amount: Balance,
+        ) -> Result<Vec<(AccountId, Balance)>> {
+            let payees: Vec<(AccountId, u16)> = if self.payees.is_empty() {
+                ink::prelude::vec![(self.treasury, BASIS_POINTS_TOTAL)]
+            } else {
+                self.payees.clone()
+            };
+            let mut shares: Vec<(AccountId, Balance)> = payees
+                .iter()
+                .map(|(payee, basis_points)| {
+                    let share: Balance = (U256::from(amount) * U256::from(*basis_points)
+                        / U256::from(BASIS_POINTS_TOTAL))
+                    .as_u128();
+                    (*payee, share)
+                })
+                .collect();
+            let distributed: Balance = shares.iter().map(|(_, share)| share).sum();
+            shares[0].1 += amount - distributed;
+            for (payee, share) in shares.iter() {
+                if *share == 0 {
+                    continue;
+                }
+                self.pay(asset, *payee, *share)?;
+                let payee_total: Balance = self.payee_total(*payee, asset);
+                self.payee_totals
+                    .insert((*payee, asset), &(payee_total + share));
+            }
+
+            Ok(shares)
+        }
+
+        fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+            let mut output: [u8; 32] = [0; 32];
+            let pair: [[u8; 32]; 2] = if a <= b { [*a, *b] } else { [*b, *a] };
+            ink::env::hash_bytes::<Blake2x256>(&pair.concat(), &mut output);
+            output
+        }
+
+        // Proceeds stay in the contract when asked to, or until the soft cap is reached
+        fn holds_proceeds(&self) -> bool {
+            self.hold_proceeds || self.in_soft_cap.is_some()
+        }
+
+        // Only meaningful once the sale has started, shared by buy and status
+        fn in_whitelist_phase(&self, block_timestamp: Timestamp) -> bool {
+            self.whitelist_duration > 0 && block_timestamp < (self.start + self.whitelist_duration)
+        }
+
+        // Sends amount straight away, or credits it to withdraw when proceeds are held
+        fn pay(&mut self, asset: Option<AccountId>, to: AccountId, amount: Balance) -> Result<()> {
+            if self.holds_proceeds() {
+                let withdrawable: Balance = self.withdrawable(to, asset);
+                self.withdrawable
+                    .insert((to, asset), &(withdrawable + amount));
+                Ok(())
+            } else {
+                self.transfer_asset(asset, to, amount)
+            }
+        }
+
+        // Shared by buy and quote so that the two can not drift apart
+        fn prepare_buy(
+            &self,
+            address: AccountId,
+            mut in_amount: Balance,
+            whitelist_proof: Option<WhitelistProof>,
+            block_timestamp: Timestamp,
+        ) -> Result<Purchase> {
+            self.validate_on_sale(block_timestamp)?;
+            // validate user is on whitelist if during whitelist duration
+            let mut buyer: Buyer = self.show(address);
+            if let Some(whitelist_proof_unwrapped) = whitelist_proof {
+                if !buyer.whitelisted {
+                    self.verify_whitelist_proof(address, &whitelist_proof_unwrapped)?;
+                    buyer.whitelisted = true;
+                    buyer.whitelist_allocation = whitelist_proof_unwrapped.allocation;
+                }
+            }
+            let whitelist_phase: bool = self.in_whitelist_phase(block_timestamp);
+            if whitelist_phase {
+                if !buyer.whitelisted {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Sale is currently only available to whitelisted addresses".to_string(),
+                    ));
+                }
+            }
+            // validate in amount is in units of in_unit
+            if in_amount == 0 || in_amount % self.in_unit > 0 {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In amount must be in multiples of in_unit".to_string(),
+                ));
+            }
+            // validate sold out
+            if self.in_raised == self.in_target {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sold out".to_string(),
+                ));
+            }
+            // validate per buyer limits
+            if buyer.total_in + in_amount < self.min_in_per_buyer {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In amount is below the minimum per buyer".to_string(),
+                ));
+            }
+            let mut max_in_amount: Balance = self.in_target - self.in_raised;
+            if let Some(max_in_per_buyer) = self.max_in_per_buyer {
+                if buyer.total_in >= max_in_per_buyer {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Maximum per buyer reached".to_string(),
+                    ));
+                }
+                max_in_amount = max_in_amount.min(max_in_per_buyer - buyer.total_in);
+            }
+            // unsold whitelist allocation rolls into the public phase as in_target is shared
+            if whitelist_phase {
+                if let Some(whitelist_in_target) = self.whitelist_in_target {
+                    if self.whitelist_in_raised >= whitelist_in_target {
+                        return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                            "Whitelist allocation sold out".to_string(),
+                        ));
+                    }
+                    max_in_amount =
+                        max_in_amount.min(whitelist_in_target - self.whitelist_in_raised);
+                }
+                if let Some(whitelist_allocation) = buyer.whitelist_allocation {
+                    if buyer.total_in >= whitelist_allocation {
+                        return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                            "Whitelist allocation reached".to_string(),
+                        ));
+                    }
+                    max_in_amount = max_in_amount.min(whitelist_allocation - buyer.total_in);
+                }
+            }
+            let mut refund_amount: Balance = 0;
+            if in_amount > max_in_amount {
+                refund_amount = in_amount - max_in_amount;
+                in_amount = max_in_amount;
+                // a partial fill below the minimum is only allowed when it takes the rest of in_target
+                if buyer.total_in + in_amount < self.min_in_per_buyer
+                    && in_amount < self.in_target - self.in_raised
+                {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "In amount is below the minimum per buyer".to_string(),
+                    ));
+                }
+            }
+            let out_amount: Balance = self.calculate_out_amount(in_amount, block_timestamp);
+            let out_bonus: Balance =
+                self.calculate_out_bonus(in_amount, out_amount, block_timestamp);
+
+            Ok(Purchase {
+                quote: Quote {
+                    in_amount,
+                    refund_amount,
+                    out_amount,
+                    out_bonus,
+                },
+                buyer,
+                whitelist_phase,
+            })
+        }
+
+        fn psp22_transfer(&self, token: AccountId, to: AccountId, amount: Balance) -> Result<()> {
+            build_call::<super::az_token_sale_to_airdrop::Environment>()
+                .call_type(Call::new(token))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                        .push_arg(to)
+                        .push_arg(amount)
+                        .push_arg(Vec::<u8>::new()),
+                )
+                .call_flags(CallFlags::default())
+                .returns::<core::result::Result<(), PSP22Error>>()
+                .invoke()?;
+
+            Ok(())
+        }
+
+        fn psp22_transfer_from(
+            &self,
+            token: AccountId,
+            from: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<()> {
+            build_call::<super::az_token_sale_to_airdrop::Environment>()
+                .call_type(Call::new(token))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                        "PSP22::transfer_from"
+                    )))
+                    .push_arg(from)
+                    .push_arg(to)
+                    .push_arg(amount)
+                    .push_arg(Vec::<u8>::new()),
+                )
+                .call_flags(CallFlags::default())
+                .returns::<core::result::Result<(), PSP22Error>>()
+                .invoke()?;
+
+            Ok(())
+        }
+
+        // Returns (in commission, out bonus) for the referrer
+        fn referral_bonus_amounts(
+            &self,
+            in_amount: Balance,
+            out_amount: Balance,
+        ) -> (Balance, Balance) {
+            let basis_points_of = |amount: Balance, basis_points: u16| -> Balance {
+                (U256::from(amount) * U256::from(basis_points) / U256::from(BASIS_POINTS_TOTAL))
+                    .as_u128()
+            };
+            match self.referral_bonus {
+                Some(ReferralBonus::In(basis_points)) => {
+                    (basis_points_of(in_amount, basis_points), 0)
+                }
+                Some(ReferralBonus::Out(basis_points)) => {
+                    (0, basis_points_of(out_amount, basis_points))
+                }
+                None => (0, 0),
+            }
+        }
+
+        // payer is None for off-chain purchases, which do not emit a buy event
+        fn settle_buy(
+            &mut self,
+            address: AccountId,
+            payer: Option<AccountId>,
+            purchase: Purchase,
+            payment_asset: Option<AccountId>,
+            block_timestamp: Timestamp,
+        ) -> Result<(Balance, Balance, Balance)> {
+            let Purchase {
+                quote,
+                mut buyer,
+                whitelist_phase,
+            } = purchase;
+            self.announce_status(block_timestamp);
+            // Add amount to airdrop contract
+            let recipient: Recipient =
+                self.airdrop_recipient_add(address, quote.out_amount + quote.out_bonus)?;
+            // A new recipient takes the phase's vesting terms. One with an earlier allocation,
+            // from any phase or sale round, must already be on them so they are never rewritten.
+            if let Some(vesting_terms) = self.vesting_terms_for(whitelist_phase) {
+                if recipient.total_amount == quote.out_amount + quote.out_bonus {
+                    self.airdrop_recipient_vesting_update(address, vesting_terms)?;
+                } else {
+                    Self::validate_vesting_terms(&recipient, vesting_terms)?;
+                }
+            }
+            self.in_raised += quote.in_amount;
+            if whitelist_phase {
+                self.whitelist_in_raised += quote.in_amount;
+            }
+            buyer.total_in += quote.in_amount;
+            buyer.total_out += quote.out_amount + quote.out_bonus;
+            self.buyers.insert(address, &buyer);
+
+            // emit event
+            if let Some(payer) = payer {
+                Self::emit_event(
+                    self.env(),
+                    Event::Buy(Buy {
+                        buyer: address,
+                        payer,
+                        payment_asset,
+                        in_amount: quote.in_amount,
+                        out_amount: quote.out_amount,
+                        out_bonus: quote.out_bonus,
+                        refund_amount: quote.refund_amount,
+                        in_raised: self.in_raised,
+                    }),
+                );
+            }
+            self.announce_status(block_timestamp);
+
+            Ok((quote.in_amount, quote.out_amount, quote.out_bonus))
+        }
+
+        fn soft_cap(&self) -> Result<Balance> {
+            self.in_soft_cap
+                .ok_or(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale does not have a soft cap".to_string(),
+                ))
+        }
+
+        fn emit_event<EE: EmitEvent<Self>>(emitter: EE, event: Event) {
+            emitter.emit_event(event);
+        }
+
+        fn status_at(&self, block_timestamp: Timestamp) -> Status {
+            if self.finalized_at.is_some() {
+                Status::Finalized
+            } else if block_timestamp < self.start {
+                Status::Pending
+            } else if block_timestamp > self.end {
+                Status::Ended
+            } else if self.in_raised == self.in_target {
+                Status::SoldOut
+            } else if self.in_whitelist_phase(block_timestamp) {
+                Status::Whitelist
+            } else {
+                Status::Public
+            }
+        }
+
+        // None is AZERO, otherwise the PSP22 token
+        fn transfer_asset(
+            &self,
+            asset: Option<AccountId>,
+            address: AccountId,
+            amount: Balance,
+        ) -> Result<()> {
+            match asset {
+                Some(token) => self.psp22_transfer(token, address, amount),
+                None => self.transfer_azero(address, amount),
+            }
+        }
+
+        // Reverses what the buyer's referred buys credited to the referrer.
+        // Returns the referrer and the out bonus to remove from the airdrop contract.
+        fn unwind_referral(&mut self, buyer: &mut Buyer) -> Option<(AccountId, Balance)> {
+            let referrer: AccountId = buyer.referrer.take()?;
+            let mut referrer_totals: Referrer = self.referrer(referrer);
+            referrer_totals.in_referred -= buyer.referred_in;
+            referrer_totals.buyers_referred -= 1;
+            referrer_totals.in_commission -= buyer.referrer_in_commission;
+            referrer_totals.out_bonus -= buyer.referrer_out_bonus;
+            self.referrers.insert(referrer, &referrer_totals);
+            // Refunds need a soft cap, so the commission is still held for withdrawal
+            if buyer.referrer_in_commission > 0 {
+                let withdrawable: Balance = self.withdrawable(referrer, self.in_token);
+                self.withdrawable.insert(
+                    (referrer, self.in_token),
+                    &(withdrawable - buyer.referrer_in_commission),
+                );
+            }
+            let out_bonus: Balance = buyer.referrer_out_bonus;
+            buyer.referred_in = 0;
+            buyer.referrer_in_commission = 0;
+            buyer.referrer_out_bonus = 0;
+
+            Some((referrer, out_bonus))
+        }
+
+        fn validate_before_start(&self) -> Result<()> {
+            if Self::env().block_timestamp() >= self.start {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has started".to_string(),
+                ));
+            }
+
+            Ok(())
+        }
+
+        fn validate_not_finalized(&self) -> Result<()> {
+            if self.finalized_at.is_some() {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is finalized".to_string(),
+                ));
+            }
+
+            Ok(())
+        }
+
+        // Checks that apply to every buyer, so a batch can be rejected before any entry
+        fn validate_on_sale(&self, block_timestamp: Timestamp) -> Result<()> {
+            // validate sale has not been finalized
+            self.validate_not_finalized()?;
+            // validate sale is not paused
+            if self.paused {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is paused".to_string(),
+                ));
+            }
+            // validate sale has started
+            if block_timestamp < self.start {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has not started".to_string(),
+                ));
+            }
+            // validate sale has not ended
+            if block_timestamp > self.end {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has ended".to_string(),
+                ));
+            }
+
+            Ok(())
+        }
+
+        fn validate_schedule(
+            start: Timestamp,
+            whitelist_duration: Timestamp,
+            end: Timestamp,
+            price_step_duration: Timestamp,
+        ) -> Result<()> {
+            if start + whitelist_duration >= end {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Start + whitelist_duration must be less than end".to_string(),
+                ));
+            }
+            if price_step_duration > end - start {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Price step duration must not exceed sale duration".to_string(),
+                ));
+            }
+
+            Ok(())
+        }
+
+        fn validate_vesting_terms(
+            recipient: &Recipient,
+            vesting_terms: VestingTerms,
+        ) -> Result<()> {
+            let applied_vesting_terms: VestingTerms = VestingTerms {
+                collectable_at_tge_percentage: recipient.collectable_at_tge_percentage,
+                cliff_duration: recipient.cliff_duration,
+                vesting_duration: recipient.vesting_duration,
+            };
+            if applied_vesting_terms != vesting_terms {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Vesting terms differ from those already applied".to_string(),
+                ));
+            }
+
+            Ok(())
+        }
+
+        fn verify_whitelist_proof(
+            &self,
+            address: AccountId,
+            whitelist_proof: &WhitelistProof,
+        ) -> Result<()> {
+            let Some(whitelist_merkle_root) = self.whitelist_merkle_root else {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Whitelist merkle root not set".to_string(),
+                ));
+            };
+            let leaf: [u8; 32] = Self::whitelist_leaf(address, whitelist_proof.allocation);
+            let computed_root: [u8; 32] = whitelist_proof
+                .proof
+                .iter()
+                .fold(leaf, |hash, sibling| Self::hash_pair(&hash, sibling));
+            if computed_root != whitelist_merkle_root {
+                return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Invalid whitelist proof".to_string(),
+                ));
+            }
+            if let Some(allocation) = whitelist_proof.allocation {
+                if allocation % self.in_unit > 0 {
+                    return Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Whitelist allocation must be in multiples of in_unit".to_string(),
+                    ));
+                }
+            }
+
+            Ok(())
+        }
+
+        // Whitelist buyers use the whitelist terms when set, falling back to the sale's terms
+        fn vesting_terms_for(&self, whitelist_phase: bool) -> Option<VestingTerms> {
+            if whitelist_phase {
+                self.whitelist_vesting_terms.or(self.vesting_terms)
+            } else {
+                self.vesting_terms
+            }
+        }
+
+        fn whitelist_leaf(address: AccountId, allocation: Option<Balance>) -> [u8; 32] {
+            let mut output: [u8; 32] = [0; 32];
+            ink::env::hash_encoded::<Blake2x256, _>(&(address, allocation), &mut output);
+            output
+        }
+
+        // Returns None when the address is already in the requested state
+        fn whitelist_set(&mut self, address: AccountId, whitelisted: bool) -> Option<Buyer> {
+            let mut buyer: Buyer = self.show(address);
+            if buyer.whitelisted == whitelisted {
+                return None;
+            }
+
+            buyer.whitelisted = whitelisted;
+            if !whitelisted {
+                buyer.whitelist_allocation = None;
+            }
+            self.buyers.insert(address, &buyer);
+
+            // emit event
+            if whitelisted {
+                Self::emit_event(self.env(), Event::WhitelistAdd(WhitelistAdd { address }));
+            } else {
+                Self::emit_event(
+                    self.env(),
+                    Event::WhitelistRemove(WhitelistRemove { address }),
+                );
+            }
+
+            Some(buyer)
+        }
+
+        fn whitelist_set_many(
+            &mut self,
+            addresses: Vec<AccountId>,
+            whitelisted: bool,
+        ) -> WhitelistUpdateSummary {
+            let mut summary = WhitelistUpdateSummary {
+                changed: Vec::new(),
+                skipped: Vec::new(),
+            };
+            for address in addresses {
+                if self.whitelist_set(address, whitelisted).is_some() {
+                    summary.changed.push(address)
+                } else {
+                    summary.skipped.push(address)
+                }
+            }
+
+            summary
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::{
+            test::{default_accounts, recorded_events, set_caller, DefaultAccounts},
+            DefaultEnvironment,
+        };
+
+        const MOCK_IN_UNIT: Balance = 10;
+        const MOCK_OUT_UNIT: Balance = 500;
+        const MOCK_START: Timestamp = 654_654;
+        const MOCK_END: Timestamp = 754_654;
+        const MOCK_WHITELIST_DURATION: Timestamp = 1_000;
+        const MOCK_IN_TARGET: Balance = 50_000_000_000_000_000;
+        const MOCK_MIN_IN_PER_BUYER: Balance = MOCK_IN_UNIT * 2;
+        const MOCK_MAX_IN_PER_BUYER: Option<Balance> = Some(MOCK_IN_TARGET / 2);
+        const MOCK_WHITELIST_IN_TARGET: Option<Balance> = Some(MOCK_IN_TARGET / 5);
+        const MOCK_IN_SOFT_CAP: Option<Balance> = Some(MOCK_IN_TARGET / 2);
+        const MOCK_OUT_UNIT_END: Option<Balance> = None;
+        const MOCK_PRICE_STEP_DURATION: Timestamp = 0;
+        const MOCK_IN_TOKEN: Option<AccountId> = None;
+        const MOCK_HOLD_PROCEEDS: bool = false;
+
+        // === HELPERS ===
+        fn mock_price_tiers() -> Vec<PriceTier> {
+            vec![
+                PriceTier {
+                    in_amount: MOCK_IN_UNIT * 10,
+                    out_unit: MOCK_OUT_UNIT * 2,
+                },
+                PriceTier {
+                    in_amount: MOCK_IN_UNIT * 10,
+                    out_unit: MOCK_OUT_UNIT * 3 / 2,
+                },
+            ]
+        }
+
+        fn init() -> (DefaultAccounts<DefaultEnvironment>, AzTokenSaleToAirdrop) {
+            let accounts = default_accounts();
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            let az_token_sale_to_airdrop = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                mock_price_tiers(),
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            (accounts, az_token_sale_to_airdrop.expect("REASON"))
+        }
+
+        fn mock_airdrop_smart_contract() -> AccountId {
+            let accounts: DefaultAccounts<DefaultEnvironment> = default_accounts();
+            accounts.eve
+        }
+
+        fn mock_treasury() -> AccountId {
+            let accounts: DefaultAccounts<DefaultEnvironment> = default_accounts();
+            accounts.django
+        }
+
+        fn mock_payees() -> Vec<(AccountId, u16)> {
+            let accounts: DefaultAccounts<DefaultEnvironment> = default_accounts();
+            vec![(mock_treasury(), 7_500), (accounts.charlie, 2_500)]
+        }
+
+        fn last_event() -> Event {
+            let event = recorded_events().last().unwrap();
+            <Event as scale::Decode>::decode(&mut &event.data[..]).unwrap()
+        }
+
+        // === TESTS ===
+        // === TEST CONSTRUCTOR ===
+        #[ink::test]
+        fn test_new() {
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                10,
+                20,
+                10,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                mock_price_tiers(),
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            // when start + whitelist_duration is greater than or equal to end
+            // * it raises an error
+            assert!(result.is_err());
+            // when start + whitelist_duration is less than end
+            // == when in_unit is zero
+            // == * it raises an error
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                0,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                mock_price_tiers(),
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            assert!(result.is_err());
+            // == when in_unit is positive
+            // === when out_unit is zero
+            // === * it raises an error
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                0,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                mock_price_tiers(),
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            assert!(result.is_err());
+            // === when out_unit is positive
+            // ==== when in target is zero
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                0,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                mock_price_tiers(),
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            assert!(result.is_err());
+            // ==== when in target is positive
+            // ===== when in target is not a multiple of in unit
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET + 1,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                mock_price_tiers(),
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            // ===== * it raises an error
+            assert!(result.is_err());
+            // ===== when in target is a multiple of in unit
+            // ====== when min in per buyer is not a multiple of in unit
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_IN_UNIT + 1,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                mock_price_tiers(),
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            // ====== * it raises an error
+            assert!(result.is_err());
+            // ====== when min in per buyer is a multiple of in unit
+            // ======= when max in per buyer is less than min in per buyer
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                Some(MOCK_MIN_IN_PER_BUYER - MOCK_IN_UNIT),
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                mock_price_tiers(),
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            // ======= * it raises an error
+            assert!(result.is_err());
+            // ======= when max in per buyer is at least min in per buyer
+            // ======== when whitelist in target is greater than in target
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                Some(MOCK_IN_TARGET + MOCK_IN_UNIT),
+                MOCK_IN_SOFT_CAP,
+                mock_price_tiers(),
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            // ======== * it raises an error
+            assert!(result.is_err());
+            // ======== when whitelist in target is less than or equal to in target
+            // ========= when in soft cap is greater than in target
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                Some(MOCK_IN_TARGET + MOCK_IN_UNIT),
+                mock_price_tiers(),
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            // ========= * it raises an error
+            assert!(result.is_err());
+            // ========= when in soft cap is less than or equal to in target
+            // ========== when a price tier in amount is not a multiple of in unit
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                vec![PriceTier {
+                    in_amount: MOCK_IN_UNIT + 1,
+                    out_unit: MOCK_OUT_UNIT,
+                }],
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            // ========== * it raises an error
+            assert!(result.is_err());
+            // ========== when price tiers exceed in target
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                vec![PriceTier {
+                    in_amount: MOCK_IN_TARGET + MOCK_IN_UNIT,
+                    out_unit: MOCK_OUT_UNIT,
+                }],
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            // ========== * it raises an error
+            assert!(result.is_err());
+            // ========== when price tiers are valid
+            // =========== when combined with a price schedule
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                mock_price_tiers(),
+                Some(MOCK_OUT_UNIT * 2),
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            // =========== * it raises an error
+            assert!(result.is_err());
+            // =========== when price step duration is set without out unit end
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                vec![],
+                None,
+                1_000,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            // =========== * it raises an error
+            assert!(result.is_err());
+            // =========== when price schedule is valid
+            // =========== * it is valid
+            let result = AzTokenSaleToAirdrop::new(
+                mock_airdrop_smart_contract(),
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                MOCK_START,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                mock_price_tiers(),
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                mock_treasury(),
+                mock_payees(),
+                MOCK_HOLD_PROCEEDS,
+            );
+            assert!(result.is_ok());
+        }
+
+        // === TEST QUERIES ===
+        #[ink::test]
+        fn test_config() {
+            let (_accounts, az_token_sale_to_airdrop) = init();
+            let config = az_token_sale_to_airdrop.config();
+            // * it returns the config
+            assert_eq!(config.admin, az_token_sale_to_airdrop.admin);
+            assert_eq!(config.treasury, mock_treasury());
+            assert_eq!(config.payees, mock_payees());
+            assert_eq!(config.hold_proceeds, MOCK_HOLD_PROCEEDS);
+            assert_eq!(config.vesting_terms, None);
+            assert_eq!(config.whitelist_vesting_terms, None);
+            assert_eq!(
+                config.airdrop_smart_contract,
+                az_token_sale_to_airdrop.airdrop_smart_contract
+            );
+            assert_eq!(config.in_unit, az_token_sale_to_airdrop.in_unit);
+            assert_eq!(config.out_unit, az_token_sale_to_airdrop.out_unit);
+            assert_eq!(config.start, az_token_sale_to_airdrop.start);
+            assert_eq!(config.end, az_token_sale_to_airdrop.end);
+            assert_eq!(
+                config.whitelist_duration,
+                az_token_sale_to_airdrop.whitelist_duration
+            );
+            assert_eq!(config.in_target, az_token_sale_to_airdrop.in_target);
+            assert_eq!(
+                config.min_in_per_buyer,
+                az_token_sale_to_airdrop.min_in_per_buyer
+            );
+            assert_eq!(
+                config.max_in_per_buyer,
+                az_token_sale_to_airdrop.max_in_per_buyer
+            );
+            assert_eq!(
+                config.whitelist_in_target,
+                az_token_sale_to_airdrop.whitelist_in_target
+            );
+            assert_eq!(config.whitelist_in_raised, 0);
+            assert_eq!(config.public_in_raised, 0);
+            assert_eq!(config.in_soft_cap, az_token_sale_to_airdrop.in_soft_cap);
+            assert_eq!(config.in_token, az_token_sale_to_airdrop.in_token);
+            assert_eq!(config.price_tiers, mock_price_tiers());
+            assert_eq!(config.price_tier_index, Some(0));
+            assert_eq!(config.price_tier_in_remaining, MOCK_IN_UNIT * 10);
+        }
+
+        #[ink::test]
+        fn test_quote() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when buy would raise an error
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(MOCK_START);
+            // * it returns the same error
+            let mut result = az_token_sale_to_airdrop.quote(accounts.bob, MOCK_IN_UNIT * 2, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is currently only available to whitelisted addresses".to_string()
+                ))
+            );
+            // when buy would succeed
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                MOCK_START + MOCK_WHITELIST_DURATION,
+            );
+            // = when the full in amount is available
+            // = * it returns the out amount for the in amount at the current block
+            result = az_token_sale_to_airdrop.quote(accounts.bob, MOCK_IN_UNIT * 2, None);
+            assert_eq!(
+                result,
+                Ok(Quote {
+                    in_amount: MOCK_IN_UNIT * 2,
+                    refund_amount: 0,
+                    out_amount: MOCK_OUT_UNIT * 4,
+                    out_bonus: 0,
+                })
+            );
+            // = when the in amount exceeds what is available to the buyer
+            // = * it returns the refund amount
+            result = az_token_sale_to_airdrop.quote(
+                accounts.bob,
+                MOCK_MAX_IN_PER_BUYER.unwrap() + MOCK_IN_UNIT,
+                None,
+            );
+            let quote: Quote = result.unwrap();
+            assert_eq!(quote.in_amount, MOCK_MAX_IN_PER_BUYER.unwrap());
+            assert_eq!(quote.refund_amount, MOCK_IN_UNIT);
+            // = when the in amount available is below the minimum per buyer
+            // == when it is the rest of in_target
+            az_token_sale_to_airdrop.in_raised = MOCK_IN_TARGET - MOCK_IN_UNIT;
+            // == * it returns the partial fill
+            result = az_token_sale_to_airdrop.quote(accounts.bob, MOCK_MIN_IN_PER_BUYER, None);
+            assert_eq!(result.unwrap().in_amount, MOCK_IN_UNIT);
+            // == when it is not the rest of in_target
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(MOCK_START);
+            az_token_sale_to_airdrop
+                .whitelist_add(accounts.bob)
+                .unwrap();
+            az_token_sale_to_airdrop.in_raised = MOCK_WHITELIST_IN_TARGET.unwrap() - MOCK_IN_UNIT;
+            az_token_sale_to_airdrop.whitelist_in_raised = az_token_sale_to_airdrop.in_raised;
+            // == * it raises an error
+            result = az_token_sale_to_airdrop.quote(accounts.bob, MOCK_MIN_IN_PER_BUYER, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In amount is below the minimum per buyer".to_string()
+                ))
+            );
+        }
+
+        #[ink::test]
+        fn test_status() {
+            let (_accounts, mut az_token_sale_to_airdrop) = init();
+            // when sale has not started
+            // * it returns pending, changing at start
+            assert_eq!(
+                az_token_sale_to_airdrop.status(),
+                StatusSummary {
+                    status: Status::Pending,
+                    paused: false,
+                    next_status_at: Some(MOCK_START),
+                    in_remaining: MOCK_IN_TARGET,
+                }
+            );
+            // when sale is in the whitelist phase
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(MOCK_START);
+            az_token_sale_to_airdrop.in_raised = MOCK_IN_UNIT * 3;
+            az_token_sale_to_airdrop.whitelist_in_raised = MOCK_IN_UNIT * 3;
+            // * it returns whitelist, changing at the end of the whitelist duration
+            // * it limits in remaining to the whitelist allocation
+            assert_eq!(
+                az_token_sale_to_airdrop.status(),
+                StatusSummary {
+                    status: Status::Whitelist,
+                    paused: false,
+                    next_status_at: Some(MOCK_START + MOCK_WHITELIST_DURATION),
+                    in_remaining: MOCK_WHITELIST_IN_TARGET.unwrap() - MOCK_IN_UNIT * 3,
+                }
+            );
+            // when sale is in the public phase
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(
+                MOCK_START + MOCK_WHITELIST_DURATION,
+            );
+            az_token_sale_to_airdrop.paused = true;
+            // * it returns public, changing after end
+            // * it returns whether the sale is paused
+            assert_eq!(
+                az_token_sale_to_airdrop.status(),
+                StatusSummary {
+                    status: Status::Public,
+                    paused: true,
+                    next_status_at: Some(MOCK_END + 1),
+                    in_remaining: MOCK_IN_TARGET - MOCK_IN_UNIT * 3,
+                }
+            );
+            // when in target has been raised
+            az_token_sale_to_airdrop.in_raised = MOCK_IN_TARGET;
+            // * it returns sold out
+            assert_eq!(az_token_sale_to_airdrop.status().status, Status::SoldOut);
+            assert_eq!(az_token_sale_to_airdrop.status().in_remaining, 0);
+            // when sale has ended
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(MOCK_END + 1);
+            az_token_sale_to_airdrop.in_raised = MOCK_IN_UNIT * 3;
+            // * it returns ended, with nothing remaining
+            assert_eq!(
+                az_token_sale_to_airdrop.status(),
+                StatusSummary {
+                    status: Status::Ended,
+                    paused: true,
+                    next_status_at: None,
+                    in_remaining: 0,
+                }
+            );
+            // when sale has been finalized
+            az_token_sale_to_airdrop.finalized_at = Some(MOCK_END + 1);
+            // * it returns finalized
+            assert_eq!(az_token_sale_to_airdrop.status().status, Status::Finalized);
+        }
+
+        // === TEST HANDLES ===
+        #[ink::test]
+        fn test_admin_transfer_accept() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when there is no pending admin
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.admin_transfer_accept();
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when there is a pending admin
+            az_token_sale_to_airdrop
+                .admin_transfer_propose(accounts.django)
+                .unwrap();
+            // = when called by someone other than the pending admin
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.admin_transfer_accept();
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // = when called by the pending admin
+            set_caller::<DefaultEnvironment>(accounts.django);
+            result = az_token_sale_to_airdrop.admin_transfer_accept();
+            // = * it sets the pending admin as admin
+            assert_eq!(result, Ok(accounts.django));
+            assert_eq!(az_token_sale_to_airdrop.admin, accounts.django);
+            assert_eq!(az_token_sale_to_airdrop.pending_admin, None);
+            // = * it emits an AdminTransferAccept event
+            match last_event() {
+                Event::AdminTransferAccept(AdminTransferAccept {
+                    previous_admin,
+                    admin,
+                }) => {
+                    assert_eq!(previous_admin, accounts.bob);
+                    assert_eq!(admin, accounts.django);
+                }
+                _ => panic!("Expected AdminTransferAccept event"),
+            }
+        }
+
+        #[ink::test]
+        fn test_admin_transfer_cancel() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when called by admin
+            // = when there is no pending admin
+            // = * it raises an error
+            let mut result = az_token_sale_to_airdrop.admin_transfer_cancel();
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::NotFound(
+                    "Pending admin".to_string()
+                ))
+            );
+            // = when there is a pending admin
+            az_token_sale_to_airdrop
+                .admin_transfer_propose(accounts.django)
+                .unwrap();
+            // = * it removes the pending admin
+            result = az_token_sale_to_airdrop.admin_transfer_cancel();
+            assert!(result.is_ok());
+            assert_eq!(az_token_sale_to_airdrop.pending_admin, None);
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.django);
+            // * it raises an error
+            result = az_token_sale_to_airdrop.admin_transfer_cancel();
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+        }
+
+        #[ink::test]
+        fn test_admin_transfer_propose() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when called by admin
+            // = when new admin is the admin
+            // = * it raises an error
+            let mut result = az_token_sale_to_airdrop.admin_transfer_propose(accounts.bob);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "New admin must be different to admin".to_string()
+                ))
+            );
+            // = when new admin is different to the admin
+            // = * it sets the pending admin
+            result = az_token_sale_to_airdrop.admin_transfer_propose(accounts.django);
+            assert_eq!(result, Ok(accounts.django));
+            assert_eq!(
+                az_token_sale_to_airdrop.config().pending_admin,
+                Some(accounts.django)
+            );
+            // = * it does not change the admin
+            assert_eq!(az_token_sale_to_airdrop.admin, accounts.bob);
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.django);
+            // * it raises an error
+            result = az_token_sale_to_airdrop.admin_transfer_propose(accounts.django);
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+        }
+
+        #[ink::test]
+        fn test_bonus_schedule_update() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            let bonus_schedule: BonusSchedule = BonusSchedule {
+                whitelist_basis_points: 1_000,
+                early_basis_points: 500,
+                early_duration: 86_400_000,
+                volume_basis_points: 200,
+                volume_in_threshold: MOCK_IN_UNIT * 10,
+            };
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.bonus_schedule_update(Some(bonus_schedule));
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when called by admin
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            // = when a bonus is over 10,000 basis points
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.bonus_schedule_update(Some(BonusSchedule {
+                volume_basis_points: 10_001,
+                ..bonus_schedule
+            }));
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Bonus must be at most 10,000 basis points".to_string()
+                ))
+            );
+            // = when bonus schedule is valid
+            // = * it updates the bonus schedule
+            result = az_token_sale_to_airdrop.bonus_schedule_update(Some(bonus_schedule));
+            assert!(result.is_ok());
+            assert_eq!(
+                az_token_sale_to_airdrop.config().bonus_schedule,
+                Some(bonus_schedule)
+            );
+            // = * it emits a bonus schedule update event
+            let event = last_event();
+            match event {
+                Event::BonusScheduleUpdate(BonusScheduleUpdate {
+                    bonus_schedule: event_bonus_schedule,
+                }) => {
+                    assert_eq!(event_bonus_schedule, Some(bonus_schedule));
+                }
+                _ => panic!("Expected BonusScheduleUpdate event"),
+            }
+            // = when sale has started
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(MOCK_START);
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.bonus_schedule_update(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has started".to_string()
+                ))
+            );
+        }
+
+        #[ink::test]
+        fn test_buy() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when sale is denominated in a PSP22 token
+            az_token_sale_to_airdrop.in_token = Some(accounts.frank);
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.buy(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is denominated in a PSP22 token".to_string()
+                ))
+            );
+            // when sale is denominated in AZERO
+            az_token_sale_to_airdrop.in_token = None;
+            // = when sale has not started
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                az_token_sale_to_airdrop.start - 1,
+            );
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.buy(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has not started".to_string()
+                ))
+            );
+            // when sale has started
+            // = when sale has ended
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                az_token_sale_to_airdrop.end + 1,
+            );
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.buy(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has ended".to_string()
+                ))
+            );
+            // == when in whitelist phase
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                az_token_sale_to_airdrop.start + az_token_sale_to_airdrop.whitelist_duration - 1,
+            );
+            // === when buyer is not on whitelist
+            // === * it raises an error
+            result = az_token_sale_to_airdrop.buy(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is currently only available to whitelisted addresses".to_string()
+                ))
+            );
+            // ==== when buyer provides a whitelist proof
+            let whitelist_proof = WhitelistProof {
+                allocation: None,
+                proof: vec![AzTokenSaleToAirdrop::whitelist_leaf(accounts.charlie, None)],
+            };
+            // ===== when whitelist merkle root has not been set
+            // ===== * it raises an error
+            result = az_token_sale_to_airdrop.buy(Some(whitelist_proof.clone()));
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Whitelist merkle root not set".to_string()
+                ))
+            );
+            // ===== when whitelist merkle root has been set
+            // ====== when proof is invalid
+            az_token_sale_to_airdrop.whitelist_merkle_root = Some([0; 32]);
+            // ====== * it raises an error
+            result = az_token_sale_to_airdrop.buy(Some(whitelist_proof.clone()));
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Invalid whitelist proof".to_string()
+                ))
+            );
+            // ====== when proof is valid
+            // ======= when allocation is not a multiple of in_unit
+            az_token_sale_to_airdrop.whitelist_merkle_root = Some(AzTokenSaleToAirdrop::hash_pair(
+                &AzTokenSaleToAirdrop::whitelist_leaf(accounts.bob, Some(MOCK_IN_UNIT + 1)),
+                &AzTokenSaleToAirdrop::whitelist_leaf(accounts.charlie, None),
+            ));
+            // ======= * it raises an error
+            result = az_token_sale_to_airdrop.buy(Some(WhitelistProof {
+                allocation: Some(MOCK_IN_UNIT + 1),
+                proof: whitelist_proof.proof.clone(),
+            }));
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Whitelist allocation must be in multiples of in_unit".to_string()
+                ))
+            );
+            // ======= when allocation is valid
+            az_token_sale_to_airdrop.whitelist_merkle_root = Some(AzTokenSaleToAirdrop::hash_pair(
+                &AzTokenSaleToAirdrop::whitelist_leaf(accounts.bob, None),
+                &AzTokenSaleToAirdrop::whitelist_leaf(accounts.charlie, None),
+            ));
+            // ======= * it treats the buyer as whitelisted
+            result = az_token_sale_to_airdrop.buy(Some(whitelist_proof));
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In amount must be in multiples of in_unit".to_string()
+                ))
+            );
+            // === when buyer is on whitelist
+            az_token_sale_to_airdrop.buyers.insert(
+                accounts.bob,
+                &Buyer {
+                    total_in: 0,
+                    whitelisted: true,
+                    ..az_token_sale_to_airdrop.show(accounts.bob)
+                },
+            );
+            // ==== when in amount is zero
+            // ==== * it raises an error
+            result = az_token_sale_to_airdrop.buy(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In amount must be in multiples of in_unit".to_string()
+                ))
+            );
+            // ==== when in amount is positive
+            // ===== when in amount is not a multiple of in_unit
+            // ===== * it raises an error
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(MOCK_IN_UNIT + 1);
+            result = az_token_sale_to_airdrop.buy(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In amount must be in multiples of in_unit".to_string()
+                ))
+            );
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(MOCK_IN_UNIT - 1);
+            result = az_token_sale_to_airdrop.buy(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In amount must be in multiples of in_unit".to_string()
+                ))
+            );
+            // ===== when in amount is a multiple of in_unit
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(MOCK_IN_UNIT);
+            // ====== when there is no more available for sale
+            az_token_sale_to_airdrop.in_raised = az_token_sale_to_airdrop.in_target;
+            // ====== * it raises an error
+            result = az_token_sale_to_airdrop.buy(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sold out".to_string()
+                ))
+            );
+            // ====== when there is stock available
+            az_token_sale_to_airdrop.in_raised = 0;
+            // ======= when buyer total in would be below min in per buyer
+            // ======= * it raises an error
+            result = az_token_sale_to_airdrop.buy(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In amount is below the minimum per buyer".to_string()
+                ))
+            );
+            // ======= when buyer has reached max in per buyer
+            az_token_sale_to_airdrop.buyers.insert(
+                accounts.bob,
+                &Buyer {
+                    total_in: MOCK_MAX_IN_PER_BUYER.unwrap(),
+                    whitelisted: true,
+                    ..az_token_sale_to_airdrop.show(accounts.bob)
+                },
+            );
+            // ======= * it raises an error
+            result = az_token_sale_to_airdrop.buy(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Maximum per buyer reached".to_string()
+                ))
+            );
+            // ======= when whitelist allocation has sold out
+            az_token_sale_to_airdrop.buyers.insert(
+                accounts.bob,
+                &Buyer {
+                    total_in: 0,
+                    whitelisted: true,
+                    ..az_token_sale_to_airdrop.show(accounts.bob)
+                },
+            );
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(
+                MOCK_MIN_IN_PER_BUYER,
+            );
+            az_token_sale_to_airdrop.whitelist_in_raised = MOCK_WHITELIST_IN_TARGET.unwrap();
+            az_token_sale_to_airdrop.in_raised = MOCK_WHITELIST_IN_TARGET.unwrap();
+            // ======= * it raises an error
+            result = az_token_sale_to_airdrop.buy(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Whitelist allocation sold out".to_string()
+                ))
+            );
+            // REST WILL HAVE TO GO INTO INTEGRATION TEST AS IT CALLS AIRDROP SMART CONTRACT
+        }
+
+        #[ink::test]
+        fn test_buy_for() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            az_token_sale_to_airdrop
+                .whitelist_add(accounts.bob)
+                .unwrap();
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(MOCK_START);
+            ink::env::test::set_value_transferred::<DefaultEnvironment>(MOCK_MIN_IN_PER_BUYER);
+            // when in whitelist phase
+            // = when payer is whitelisted but beneficiary is not
+            // = * it raises an error
+            let result = az_token_sale_to_airdrop.buy_for(accounts.charlie, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is currently only available to whitelisted addresses".to_string()
+                ))
+            );
+            // = when beneficiary has reached their maximum
+            az_token_sale_to_airdrop
+                .whitelist_add(accounts.charlie)
+                .unwrap();
+            az_token_sale_to_airdrop.buyers.insert(
+                accounts.charlie,
+                &Buyer {
+                    total_in: MOCK_MAX_IN_PER_BUYER.unwrap(),
+                    whitelisted: true,
+                    ..az_token_sale_to_airdrop.show(accounts.charlie)
+                },
+            );
+            // = * it applies the beneficiary's limits
+            let result = az_token_sale_to_airdrop.buy_for(accounts.charlie, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Maximum per buyer reached".to_string()
+                ))
+            );
+            // REST WILL HAVE TO GO INTO INTEGRATION TEST AS IT CALLS AIRDROP SMART CONTRACT
+        }
+
+        #[ink::test]
+        fn test_buy_for_many() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(
+                MOCK_START + MOCK_WHITELIST_DURATION,
+            );
+            let purchases: Vec<(AccountId, Balance)> = vec![
+                (accounts.charlie, MOCK_MIN_IN_PER_BUYER),
+                (accounts.django, MOCK_MIN_IN_PER_BUYER * 2),
+            ];
+            ink::env::test::set_value_transferred::<DefaultEnvironment>(MOCK_MIN_IN_PER_BUYER * 3);
+            // when called by neither admin nor operator
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.buy_for_many(purchases.clone());
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when called by operator
+            az_token_sale_to_airdrop.operator = Some(accounts.charlie);
+            // = when there are no purchases
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.buy_for_many(vec![]);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Purchases can not be empty".to_string()
+                ))
+            );
+            // = when the sum of in amounts overflows
+            ink::env::test::set_value_transferred::<DefaultEnvironment>(MOCK_IN_UNIT / 2);
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.buy_for_many(vec![
+                (accounts.django, Balance::MAX - MOCK_IN_UNIT / 2 + 1),
+                (accounts.eve, MOCK_IN_UNIT),
+            ]);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sum of in amounts overflows".to_string()
+                ))
+            );
+            // = when payment does not equal the sum of in amounts
+            ink::env::test::set_value_transferred::<DefaultEnvironment>(MOCK_MIN_IN_PER_BUYER);
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.buy_for_many(purchases.clone());
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Payment must equal the sum of in amounts".to_string()
+                ))
+            );
+            // = when payment equals the sum of in amounts
+            ink::env::test::set_value_transferred::<DefaultEnvironment>(MOCK_MIN_IN_PER_BUYER * 3);
+            // == when the sale is paused
+            az_token_sale_to_airdrop.paused = true;
+            // == * it raises an error
+            result = az_token_sale_to_airdrop.buy_for_many(purchases.clone());
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is paused".to_string()
+                ))
+            );
+            az_token_sale_to_airdrop.paused = false;
+            // == when the sale has sold out
+            az_token_sale_to_airdrop.in_raised = MOCK_IN_TARGET;
+            ink::env::test::set_account_balance::<DefaultEnvironment>(
+                ink::env::test::callee::<DefaultEnvironment>(),
+                MOCK_MIN_IN_PER_BUYER * 3,
+            );
+            let original_charlie_balance: Balance =
+                ink::env::test::get_account_balance::<DefaultEnvironment>(accounts.charlie)
+                    .unwrap();
+            // == * it returns the reason for each beneficiary
+            result = az_token_sale_to_airdrop.buy_for_many(purchases);
+            assert_eq!(
+                result,
+                Ok(vec![
+                    Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Sold out".to_string()
+                    )),
+                    Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Sold out".to_string()
+                    )),
+                ])
+            );
+            // == * it refunds each beneficiary's in amount to the caller
+            assert_eq!(
+                ink::env::test::get_account_balance::<DefaultEnvironment>(accounts.charlie)
+                    .unwrap(),
+                original_charlie_balance + MOCK_MIN_IN_PER_BUYER * 3
+            );
+            // == when entries can not be bought for different reasons
+            az_token_sale_to_airdrop.in_raised = 0;
+            let mut buyer: Buyer = az_token_sale_to_airdrop.show(accounts.frank);
+            buyer.total_in = MOCK_MAX_IN_PER_BUYER.unwrap();
+            az_token_sale_to_airdrop
+                .buyers
+                .insert(accounts.frank, &buyer);
+            let purchases: Vec<(AccountId, Balance)> = vec![
+                (accounts.django, MOCK_MIN_IN_PER_BUYER + 1),
+                (accounts.eve, MOCK_MIN_IN_PER_BUYER - MOCK_IN_UNIT),
+                (accounts.frank, MOCK_MIN_IN_PER_BUYER),
+            ];
+            let in_total: Balance = MOCK_MIN_IN_PER_BUYER * 3 - MOCK_IN_UNIT + 1;
+            ink::env::test::set_value_transferred::<DefaultEnvironment>(in_total);
+            ink::env::test::set_account_balance::<DefaultEnvironment>(
+                ink::env::test::callee::<DefaultEnvironment>(),
+                in_total,
+            );
+            let original_charlie_balance: Balance =
+                ink::env::test::get_account_balance::<DefaultEnvironment>(accounts.charlie)
+                    .unwrap();
+            // == * it returns the reason for each of them and carries on with the rest of the batch
+            result = az_token_sale_to_airdrop.buy_for_many(purchases);
+            assert_eq!(
+                result,
+                Ok(vec![
+                    Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "In amount must be in multiples of in_unit".to_string()
+                    )),
+                    Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "In amount is below the minimum per buyer".to_string()
+                    )),
+                    Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Maximum per buyer reached".to_string()
+                    )),
+                ])
+            );
+            // == * it refunds each of them to the caller
+            assert_eq!(
+                ink::env::test::get_account_balance::<DefaultEnvironment>(accounts.charlie)
+                    .unwrap(),
+                original_charlie_balance + in_total
+            );
+            assert_eq!(az_token_sale_to_airdrop.in_raised, 0);
+            // REST WILL HAVE TO GO INTO INTEGRATION TEST AS IT CALLS AIRDROP SMART CONTRACT
+        }
+
+        #[ink::test]
+        fn test_buy_with_in_token() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when sale is denominated in AZERO
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.buy_with_in_token(MOCK_IN_UNIT, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is denominated in AZERO".to_string()
+                ))
+            );
+            // when sale is denominated in a PSP22 token
+            az_token_sale_to_airdrop.in_token = Some(accounts.frank);
+            // = when sale has not started
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                az_token_sale_to_airdrop.start - 1,
+            );
+            // = * it raises the same errors as buy
+            result = az_token_sale_to_airdrop.buy_with_in_token(MOCK_IN_UNIT, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has not started".to_string()
+                ))
+            );
+            // REST WILL HAVE TO GO INTO INTEGRATION TEST AS IT CALLS THE PSP22 SMART CONTRACT
+        }
+
+        #[ink::test]
+        fn test_buy_with_payment_asset() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            let token: AccountId = accounts.frank;
+            // when payment asset is not accepted
+            // * it raises an error
+            let mut result =
+                az_token_sale_to_airdrop.buy_with_payment_asset(token, MOCK_IN_UNIT, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::NotFound(
+                    "Payment asset".to_string()
+                ))
+            );
+            // when payment asset is accepted
+            az_token_sale_to_airdrop
+                .payment_asset_add(token, MOCK_IN_UNIT * 3, Some(MOCK_IN_UNIT * 6))
+                .unwrap();
+            // = when in amount is not a multiple of the payment asset's in unit
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.buy_with_payment_asset(token, MOCK_IN_UNIT, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In amount must be in multiples of in_unit".to_string()
+                ))
+            );
+            // = when in amount is a multiple of the payment asset's in unit
+            // == when payment asset has sold out
+            let mut payment_asset: PaymentAsset =
+                az_token_sale_to_airdrop.payment_assets.get(token).unwrap();
+            payment_asset.in_raised = MOCK_IN_UNIT * 6;
+            az_token_sale_to_airdrop
+                .payment_assets
+                .insert(token, &payment_asset);
+            // == * it raises an error
+            result = az_token_sale_to_airdrop.buy_with_payment_asset(token, MOCK_IN_UNIT * 3, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Payment asset sold out".to_string()
+                ))
+            );
+            // == when payment asset has not sold out
+            payment_asset.in_raised = 0;
+            az_token_sale_to_airdrop
+                .payment_assets
+                .insert(token, &payment_asset);
+            // === when sale has not started
+            // === * it raises the same errors as buy
+            result = az_token_sale_to_airdrop.buy_with_payment_asset(token, MOCK_IN_UNIT * 3, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has not started".to_string()
+                ))
+            );
+            // REST WILL HAVE TO GO INTO INTEGRATION TEST AS IT CALLS THE PSP22 SMART CONTRACT
+        }
+
+        #[ink::test]
+        fn test_buy_with_referrer() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(
+                MOCK_START + MOCK_WHITELIST_DURATION,
+            );
+            ink::env::test::set_value_transferred::<DefaultEnvironment>(MOCK_MIN_IN_PER_BUYER);
+            // when buyer refers themselves
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.buy_with_referrer(accounts.bob, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Buyer can not refer themselves".to_string()
+                ))
+            );
+            // when referrer is someone else
+            // = when buy would raise an error
+            az_token_sale_to_airdrop.paused = true;
+            // = * it raises the same error
+            result = az_token_sale_to_airdrop.buy_with_referrer(accounts.charlie, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is paused".to_string()
+                ))
+            );
+            // = when buyer already has a different referrer
+            az_token_sale_to_airdrop.paused = false;
+            let mut buyer: Buyer = az_token_sale_to_airdrop.show(accounts.bob);
+            buyer.referrer = Some(accounts.django);
+            az_token_sale_to_airdrop.buyers.insert(accounts.bob, &buyer);
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.buy_with_referrer(accounts.charlie, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Buyer already has a different referrer".to_string()
+                ))
+            );
+            // REST WILL HAVE TO GO INTO INTEGRATION TEST AS IT CALLS AIRDROP SMART CONTRACT
+        }
+
+        #[ink::test]
+        fn test_claim_refund() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // when sale has not ended
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                az_token_sale_to_airdrop.end,
+            );
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.claim_refund();
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has not ended".to_string()
+                ))
+            );
+            // when sale has ended
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                az_token_sale_to_airdrop.end + 1,
+            );
+            // = when soft cap has been reached
+            az_token_sale_to_airdrop.in_raised = MOCK_IN_SOFT_CAP.unwrap();
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.claim_refund();
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Soft cap reached".to_string()
+                ))
+            );
+            // = when soft cap has not been reached
+            az_token_sale_to_airdrop.in_raised = MOCK_IN_SOFT_CAP.unwrap() - MOCK_IN_UNIT;
+            // == when caller has nothing to refund
+            // == * it raises an error
+            result = az_token_sale_to_airdrop.claim_refund();
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Nothing to refund".to_string()
+                ))
+            );
+            // == when caller has something to refund
+            // REST WILL HAVE TO GO INTO INTEGRATION TEST AS IT CALLS AIRDROP SMART CONTRACT
+        }
+
+        #[ink::test]
+        fn test_withdraw() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when caller has nothing withdrawable
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.withdraw(None, 1, accounts.frank);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Nothing to withdraw".to_string()
+                ))
+            );
+            // when caller has something withdrawable
+            ink::env::test::set_account_balance::<DefaultEnvironment>(
+                ink::env::test::callee::<DefaultEnvironment>(),
+                MOCK_IN_SOFT_CAP.unwrap(),
+            );
+            az_token_sale_to_airdrop
+                .distribute_proceeds(None, MOCK_IN_SOFT_CAP.unwrap())
+                .unwrap();
+            let withdrawable: Balance =
+                az_token_sale_to_airdrop.withdrawable(accounts.charlie, None);
+            assert_eq!(withdrawable, MOCK_IN_SOFT_CAP.unwrap() / 4);
+            // = when amount is more than withdrawable
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.withdraw(None, withdrawable + 1, accounts.frank);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Amount must be positive and at most withdrawable".to_string()
+                ))
+            );
+            // = when soft cap has not been reached
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.withdraw(None, withdrawable, accounts.frank);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Soft cap not reached".to_string()
+                ))
+            );
+            // = when soft cap has been reached
+            az_token_sale_to_airdrop.in_raised = MOCK_IN_SOFT_CAP.unwrap();
+            let original_frank_balance: Balance =
+                ink::env::test::get_account_balance::<DefaultEnvironment>(accounts.frank).unwrap();
+            // = * it sends the amount to the given address
+            result = az_token_sale_to_airdrop.withdraw(None, withdrawable - 1, accounts.frank);
+            assert_eq!(result, Ok(withdrawable - 1));
+            assert_eq!(
+                ink::env::test::get_account_balance::<DefaultEnvironment>(accounts.frank).unwrap(),
+                original_frank_balance + withdrawable - 1
+            );
+            // = * it reduces the withdrawable amount
+            assert_eq!(
+                az_token_sale_to_airdrop.withdrawable(accounts.charlie, None),
+                1
+            );
+            assert_eq!(az_token_sale_to_airdrop.in_withdrawn, withdrawable - 1);
+            // = * it emits a withdraw event
+            let event = last_event();
+            match event {
+                Event::Withdraw(Withdraw {
+                    to,
+                    payment_asset,
+                    amount,
+                }) => {
+                    assert_eq!(to, accounts.frank);
+                    assert_eq!(payment_asset, None);
+                    assert_eq!(amount, withdrawable - 1);
+                }
+                _ => panic!("Expected Withdraw event"),
+            }
+            // = when sale has ended since the last announced status
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(MOCK_END + 1);
+            az_token_sale_to_airdrop
+                .withdraw(None, 1, accounts.frank)
+                .unwrap();
+            // = * it emits an ended event before the withdraw event
+            let events: Vec<ink::env::test::EmittedEvent> = recorded_events().collect();
+            let event: Event =
+                <Event as scale::Decode>::decode(&mut &events[events.len() - 2].data[..]).unwrap();
+            match event {
+                Event::Ended(Ended {
+                    timestamp,
+                    in_raised,
+                }) => {
+                    assert_eq!(timestamp, MOCK_END + 1);
+                    assert_eq!(in_raised, MOCK_IN_SOFT_CAP.unwrap());
+                }
+                _ => panic!("Expected Ended event"),
+            }
+        }
+
+        #[ink::test]
+        fn test_delay_start() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.delay_start(MOCK_START + 1);
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when called by admin
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            // = when new start is not after start
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.delay_start(MOCK_START);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "New start must be after start".to_string()
+                ))
+            );
+            // = when new start + whitelist duration is not before end
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.delay_start(MOCK_END - MOCK_WHITELIST_DURATION);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Start + whitelist_duration must be less than end".to_string()
+                ))
+            );
+            // = when price step duration would exceed the sale duration
+            az_token_sale_to_airdrop.out_unit_end = Some(MOCK_OUT_UNIT * 2);
+            az_token_sale_to_airdrop.price_step_duration = MOCK_END - MOCK_START;
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.delay_start(MOCK_START + 1);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Price step duration must not exceed sale duration".to_string()
+                ))
+            );
+            az_token_sale_to_airdrop.out_unit_end = MOCK_OUT_UNIT_END;
+            az_token_sale_to_airdrop.price_step_duration = MOCK_PRICE_STEP_DURATION;
+            // = when new start is valid
+            // = * it updates the start
+            result = az_token_sale_to_airdrop.delay_start(MOCK_START + 1);
+            assert!(result.is_ok());
+            assert_eq!(az_token_sale_to_airdrop.start, MOCK_START + 1);
+            // = * it emits a schedule update event
+            let event = last_event();
+            match event {
+                Event::ScheduleUpdate(ScheduleUpdate { start, end }) => {
+                    assert_eq!(start, MOCK_START + 1);
+                    assert_eq!(end, MOCK_END);
+                }
+                _ => panic!("Expected ScheduleUpdate event"),
+            }
+            // = when sale has started
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(MOCK_START + 1);
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.delay_start(MOCK_START + 2);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has started".to_string()
+                ))
+            );
+        }
+
+        #[ink::test]
+        fn test_extend_end() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.extend_end(MOCK_END + 1);
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when called by admin
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            // = when new end is not after end
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.extend_end(MOCK_END);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "New end must be after end".to_string()
+                ))
+            );
+            // = when sale has a price schedule
+            az_token_sale_to_airdrop.out_unit_end = Some(MOCK_OUT_UNIT * 2);
+            // == when sale has started
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(MOCK_START);
+            // == * it raises an error
+            result = az_token_sale_to_airdrop.extend_end(MOCK_END + 1);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Price schedule can not be extended after start".to_string()
+                ))
+            );
+            // == when sale has not started
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(MOCK_START - 1);
+            // == * it updates the end
+            result = az_token_sale_to_airdrop.extend_end(MOCK_END + 1);
+            assert!(result.is_ok());
+            az_token_sale_to_airdrop.end = MOCK_END;
+            az_token_sale_to_airdrop.out_unit_end = MOCK_OUT_UNIT_END;
+            // = when new end is valid
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(MOCK_END);
+            // = * it updates the end
+            result = az_token_sale_to_airdrop.extend_end(MOCK_END + 1);
+            assert!(result.is_ok());
+            assert_eq!(az_token_sale_to_airdrop.end, MOCK_END + 1);
+            // = * it emits a schedule update event
+            let event = last_event();
+            match event {
+                Event::ScheduleUpdate(ScheduleUpdate { start, end }) => {
+                    assert_eq!(start, MOCK_START);
+                    assert_eq!(end, MOCK_END + 1);
+                }
+                _ => panic!("Expected ScheduleUpdate event"),
+            }
+            // = when sale has ended
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(MOCK_END + 2);
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.extend_end(MOCK_END + 3);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has ended".to_string()
+                ))
+            );
+        }
+
+        #[ink::test]
+        fn test_finalize() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(
+                MOCK_START + MOCK_WHITELIST_DURATION,
+            );
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.finalize();
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when called by admin
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            az_token_sale_to_airdrop.in_raised = MOCK_IN_UNIT * 3;
+            // = * it records the final in raised and timestamp
+            result = az_token_sale_to_airdrop.finalize();
+            assert_eq!(result, Ok(MOCK_IN_UNIT * 3));
+            let config = az_token_sale_to_airdrop.config();
+            assert_eq!(
+                config.finalized_at,
+                Some(MOCK_START + MOCK_WHITELIST_DURATION)
+            );
+            assert_eq!(config.final_in_raised, Some(MOCK_IN_UNIT * 3));
+            assert_eq!(az_token_sale_to_airdrop.status().status, Status::Finalized);
+            // = * it emits a finalize event
+            let event = last_event();
+            match event {
+                Event::Finalize(Finalize {
+                    timestamp,
+                    in_raised,
+                }) => {
+                    assert_eq!(timestamp, MOCK_START + MOCK_WHITELIST_DURATION);
+                    assert_eq!(in_raised, MOCK_IN_UNIT * 3);
+                }
+                _ => panic!("Expected Finalize event"),
+            }
+            // = * it blocks buy
+            ink::env::test::set_value_transferred::<DefaultEnvironment>(MOCK_MIN_IN_PER_BUYER);
+            assert_eq!(
+                az_token_sale_to_airdrop.buy(None),
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is finalized".to_string()
+                ))
+            );
+            // = when sale is already finalized
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.finalize();
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is finalized".to_string()
+                ))
+            );
+        }
+
+        #[ink::test]
+        fn test_operator_update() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.operator_update(Some(accounts.charlie));
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when called by admin
             set_caller::<DefaultEnvironment>(accounts.bob);
-            let az_token_sale_to_airdrop = AzTokenSaleToAirdrop::new(
-                mock_airdrop_smart_contract(),
-                MOCK_IN_UNIT,
-                MOCK_OUT_UNIT,
-                MOCK_START,
-                MOCK_END,
-                MOCK_WHITELIST_DURATION,
-                MOCK_IN_TARGET,
+            // * it updates the operator
+            result = az_token_sale_to_airdrop.operator_update(Some(accounts.charlie));
+            assert!(result.is_ok());
+            assert_eq!(
+                az_token_sale_to_airdrop.config().operator,
+                Some(accounts.charlie)
             );
-            (accounts, az_token_sale_to_airdrop.expect("REASON"))
+            // * it emits an operator update event
+            let event = last_event();
+            match event {
+                Event::OperatorUpdate(OperatorUpdate { operator }) => {
+                    assert_eq!(operator, Some(accounts.charlie));
+                }
+                _ => panic!("Expected OperatorUpdate event"),
+            }
         }
 
-        fn mock_airdrop_smart_contract() -> AccountId {
-            let accounts: DefaultAccounts<DefaultEnvironment> = default_accounts();
-            accounts.eve
+        #[ink::test]
+        fn test_pause() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.pause(None);
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when called by admin
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            // = when sale is not paused
+            // = * it pauses the sale with the reason
+            result = az_token_sale_to_airdrop.pause(Some("Pricing issue".to_string()));
+            assert!(result.is_ok());
+            let config = az_token_sale_to_airdrop.config();
+            assert!(config.paused);
+            assert_eq!(config.pause_reason, Some("Pricing issue".to_string()));
+            // = * it emits a pause event
+            let event = last_event();
+            match event {
+                Event::Pause(Pause { reason }) => {
+                    assert_eq!(reason, Some("Pricing issue".to_string()));
+                }
+                _ => panic!("Expected Pause event"),
+            }
+            // = * it blocks buy
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(
+                MOCK_START + MOCK_WHITELIST_DURATION,
+            );
+            ink::env::test::set_value_transferred::<DefaultEnvironment>(MOCK_MIN_IN_PER_BUYER);
+            assert_eq!(
+                az_token_sale_to_airdrop.buy(None),
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is paused".to_string()
+                ))
+            );
+            // = when sale is already paused
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.pause(None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale is already paused".to_string()
+                ))
+            );
         }
 
-        // === TESTS ===
-        // === TEST CONSTRUCTOR ===
         #[ink::test]
-        fn test_new() {
-            let result = AzTokenSaleToAirdrop::new(
-                mock_airdrop_smart_contract(),
-                MOCK_IN_UNIT,
-                MOCK_OUT_UNIT,
-                10,
-                20,
-                10,
-                MOCK_IN_TARGET,
-            );
-            // when start + whitelist_duration is greater than or equal to end
+        fn test_payment_asset_add() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            let token: AccountId = accounts.frank;
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
             // * it raises an error
-            assert!(result.is_err());
-            // when start + whitelist_duration is less than end
-            // == when in_unit is zero
-            // == * it raises an error
-            let result = AzTokenSaleToAirdrop::new(
-                mock_airdrop_smart_contract(),
-                0,
-                MOCK_OUT_UNIT,
-                MOCK_START,
-                MOCK_END,
-                MOCK_WHITELIST_DURATION,
-                MOCK_IN_TARGET,
+            let mut result = az_token_sale_to_airdrop.payment_asset_add(token, MOCK_IN_UNIT, None);
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when called by admin
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            // = when sale has started
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(MOCK_START);
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.payment_asset_add(token, MOCK_IN_UNIT, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Sale has started".to_string()
+                ))
             );
-            assert!(result.is_err());
-            // == when in_unit is positive
-            // === when out_unit is zero
-            // === * it raises an error
-            let result = AzTokenSaleToAirdrop::new(
-                mock_airdrop_smart_contract(),
+            // = when sale has not started
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(MOCK_START - 1);
+            // == when in target is not a multiple of in unit
+            // == * it raises an error
+            result = az_token_sale_to_airdrop.payment_asset_add(
+                token,
                 MOCK_IN_UNIT,
-                0,
-                MOCK_START,
-                MOCK_END,
-                MOCK_WHITELIST_DURATION,
-                MOCK_IN_TARGET,
+                Some(MOCK_IN_UNIT + 1),
             );
-            assert!(result.is_err());
-            // === when out_unit is positive
-            // ==== when in target is zero
-            let result = AzTokenSaleToAirdrop::new(
-                mock_airdrop_smart_contract(),
-                MOCK_IN_UNIT,
-                MOCK_OUT_UNIT,
-                MOCK_START,
-                MOCK_END,
-                MOCK_WHITELIST_DURATION,
-                0,
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "In target must be a positive multiple of in unit".to_string()
+                ))
             );
-            assert!(result.is_err());
-            // ==== when in target is positive
-            // ===== when in target is not a multiple of in unit
-            let result = AzTokenSaleToAirdrop::new(
-                mock_airdrop_smart_contract(),
-                MOCK_IN_UNIT,
-                MOCK_OUT_UNIT,
-                MOCK_START,
-                MOCK_END,
-                MOCK_WHITELIST_DURATION,
-                MOCK_IN_TARGET + 1,
+            // == when params are valid
+            result = az_token_sale_to_airdrop.payment_asset_add(token, MOCK_IN_UNIT, None);
+            // == * it adds the payment asset
+            assert!(result.is_ok());
+            assert_eq!(
+                az_token_sale_to_airdrop.config().payment_assets,
+                vec![(token, result.unwrap())]
             );
-            // ===== * it raises an error
-            assert!(result.is_err());
-            // ===== when in target is a multiple of in unit
-            // ===== * it is valid
-            let result = AzTokenSaleToAirdrop::new(
-                mock_airdrop_smart_contract(),
-                MOCK_IN_UNIT,
-                MOCK_OUT_UNIT,
-                MOCK_START,
-                MOCK_END,
-                MOCK_WHITELIST_DURATION,
-                MOCK_IN_TARGET,
+            // == when payment asset is already accepted
+            // == * it raises an error
+            result = az_token_sale_to_airdrop.payment_asset_add(token, MOCK_IN_UNIT, None);
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Payment asset already accepted".to_string()
+                ))
             );
-            assert!(result.is_ok());
         }
 
-        // === TEST QUERIES ===
         #[ink::test]
-        fn test_config() {
-            let (_accounts, az_token_sale_to_airdrop) = init();
-            let config = az_token_sale_to_airdrop.config();
-            // * it returns the config
-            assert_eq!(config.admin, az_token_sale_to_airdrop.admin);
-            assert_eq!(
-                config.airdrop_smart_contract,
-                az_token_sale_to_airdrop.airdrop_smart_contract
-            );
-            assert_eq!(config.in_unit, az_token_sale_to_airdrop.in_unit);
-            assert_eq!(config.out_unit, az_token_sale_to_airdrop.out_unit);
-            assert_eq!(config.start, az_token_sale_to_airdrop.start);
-            assert_eq!(config.end, az_token_sale_to_airdrop.end);
+        fn test_payment_asset_remove() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            let token: AccountId = accounts.frank;
+            // when payment asset is not accepted
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.payment_asset_remove(token);
             assert_eq!(
-                config.whitelist_duration,
-                az_token_sale_to_airdrop.whitelist_duration
+                result,
+                Err(AzTokenSaleToAirdropError::NotFound(
+                    "Payment asset".to_string()
+                ))
             );
-            assert_eq!(config.in_target, az_token_sale_to_airdrop.in_target);
+            // when payment asset is accepted
+            az_token_sale_to_airdrop
+                .payment_asset_add(token, MOCK_IN_UNIT, None)
+                .unwrap();
+            // = when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.payment_asset_remove(token);
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // = when called by admin
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            // = * it removes the payment asset
+            result = az_token_sale_to_airdrop.payment_asset_remove(token);
+            assert!(result.is_ok());
+            assert!(az_token_sale_to_airdrop.payment_assets.get(token).is_none());
+            assert!(az_token_sale_to_airdrop.config().payment_assets.is_empty());
         }
 
-        // === TEST HANDLES ===
         #[ink::test]
-        fn test_buy() {
+        fn test_record_offchain_purchase() {
             let (accounts, mut az_token_sale_to_airdrop) = init();
-            // when sale has not started
-            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
-                az_token_sale_to_airdrop.start - 1,
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(
+                MOCK_START + MOCK_WHITELIST_DURATION,
             );
+            let reference: [u8; 32] = [1; 32];
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
             // * it raises an error
-            let mut result = az_token_sale_to_airdrop.buy();
+            let mut result = az_token_sale_to_airdrop.record_offchain_purchase(
+                accounts.charlie,
+                MOCK_MIN_IN_PER_BUYER,
+                reference,
+            );
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when called by admin
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            // = when reference has already been recorded
+            az_token_sale_to_airdrop
+                .offchain_references
+                .insert(reference, &accounts.django);
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.record_offchain_purchase(
+                accounts.charlie,
+                MOCK_MIN_IN_PER_BUYER,
+                reference,
+            );
             assert_eq!(
                 result,
                 Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "Sale has not started".to_string()
+                    "Reference already recorded".to_string()
                 ))
             );
-            // when sale has started
-            // = when sale has ended
-            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
-                az_token_sale_to_airdrop.end + 1,
+            assert_eq!(
+                az_token_sale_to_airdrop.offchain_reference(reference),
+                Some(accounts.django)
             );
+            // = when in equivalent exceeds what the beneficiary can buy
             // = * it raises an error
-            result = az_token_sale_to_airdrop.buy();
+            result = az_token_sale_to_airdrop.record_offchain_purchase(
+                accounts.charlie,
+                MOCK_MAX_IN_PER_BUYER.unwrap() + MOCK_IN_UNIT,
+                [2; 32],
+            );
             assert_eq!(
                 result,
                 Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "Sale has ended".to_string()
+                    "In equivalent exceeds what beneficiary can buy".to_string()
                 ))
             );
-            // == when in whitelist phase
-            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
-                az_token_sale_to_airdrop.start + az_token_sale_to_airdrop.whitelist_duration - 1,
+            // = when buy would raise an error
+            // = * it raises the same error
+            result = az_token_sale_to_airdrop.record_offchain_purchase(
+                accounts.charlie,
+                MOCK_IN_UNIT + 1,
+                [2; 32],
             );
-            // === when buyer is not on whitelist
-            // === * it raises an error
-            result = az_token_sale_to_airdrop.buy();
             assert_eq!(
                 result,
                 Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "Sale is currently only available to whitelisted addresses".to_string()
+                    "In amount must be in multiples of in_unit".to_string()
                 ))
             );
-            // === when buyer is on whitelist
-            az_token_sale_to_airdrop.buyers.insert(
-                accounts.bob,
-                &Buyer {
-                    total_in: 0,
-                    whitelisted: true,
-                },
+            // REST WILL HAVE TO GO INTO INTEGRATION TEST AS IT CALLS AIRDROP SMART CONTRACT
+        }
+
+        #[ink::test]
+        fn test_referral_bonus_update() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            let mut result =
+                az_token_sale_to_airdrop.referral_bonus_update(Some(ReferralBonus::In(500)));
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when called by admin
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            // = when basis points are zero or over 10,000
+            // = * it raises an error
+            for referral_bonus in [ReferralBonus::Out(0), ReferralBonus::In(10_001)] {
+                result = az_token_sale_to_airdrop.referral_bonus_update(Some(referral_bonus));
+                assert_eq!(
+                    result,
+                    Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                        "Referral bonus must be between 1 and 10,000 basis points".to_string()
+                    ))
+                );
+            }
+            // = when referral bonus is valid
+            // = * it updates the referral bonus
+            result = az_token_sale_to_airdrop.referral_bonus_update(Some(ReferralBonus::In(500)));
+            assert!(result.is_ok());
+            assert_eq!(
+                az_token_sale_to_airdrop.config().referral_bonus,
+                Some(ReferralBonus::In(500))
             );
-            // ==== when in amount is zero
-            // ==== * it raises an error
-            result = az_token_sale_to_airdrop.buy();
+            // = * it emits a referral bonus update event
+            let event = last_event();
+            match event {
+                Event::ReferralBonusUpdate(ReferralBonusUpdate { referral_bonus }) => {
+                    assert_eq!(referral_bonus, Some(ReferralBonus::In(500)));
+                }
+                _ => panic!("Expected ReferralBonusUpdate event"),
+            }
+            // = when sale has started
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(MOCK_START);
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.referral_bonus_update(None);
             assert_eq!(
                 result,
                 Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "In amount must be in multiples of in_unit".to_string()
+                    "Sale has started".to_string()
                 ))
             );
-            // ==== when in amount is positive
-            // ===== when in amount is not a multiple of in_unit
-            // ===== * it raises an error
-            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(MOCK_IN_UNIT + 1);
-            result = az_token_sale_to_airdrop.buy();
+        }
+
+        #[ink::test]
+        fn test_unpause() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            let mut result = az_token_sale_to_airdrop.unpause();
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when called by admin
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            // = when sale is not paused
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.unpause();
             assert_eq!(
                 result,
                 Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "In amount must be in multiples of in_unit".to_string()
+                    "Sale is not paused".to_string()
                 ))
             );
-            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(MOCK_IN_UNIT - 1);
-            result = az_token_sale_to_airdrop.buy();
+            // = when sale is paused
+            az_token_sale_to_airdrop
+                .pause(Some("Pricing issue".to_string()))
+                .unwrap();
+            // = * it unpauses the sale and clears the reason
+            result = az_token_sale_to_airdrop.unpause();
+            assert!(result.is_ok());
+            let config = az_token_sale_to_airdrop.config();
+            assert!(!config.paused);
+            assert_eq!(config.pause_reason, None);
+            // = * it emits an unpause event
+            let event = last_event();
+            match event {
+                Event::Unpause(Unpause {}) => {}
+                _ => panic!("Expected Unpause event"),
+            }
+        }
+
+        #[ink::test]
+        fn test_vesting_terms_update() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            let vesting_terms: VestingTerms = VestingTerms {
+                collectable_at_tge_percentage: 20,
+                cliff_duration: 1_000,
+                vesting_duration: 10_000,
+            };
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            let mut result =
+                az_token_sale_to_airdrop.vesting_terms_update(Some(vesting_terms), None);
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+            // when called by admin
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            // = when collectable at tge percentage is over 100
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.vesting_terms_update(
+                Some(vesting_terms),
+                Some(VestingTerms {
+                    collectable_at_tge_percentage: 101,
+                    ..vesting_terms
+                }),
+            );
             assert_eq!(
                 result,
                 Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "In amount must be in multiples of in_unit".to_string()
+                    "Collectable at tge percentage must be at most 100".to_string()
                 ))
             );
-            // ===== when in amount is a multiple of in_unit
-            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(MOCK_IN_UNIT);
-            // ====== when there is no more available for sale
-            az_token_sale_to_airdrop.in_raised = az_token_sale_to_airdrop.in_target;
-            // ====== * it raises an error
-            result = az_token_sale_to_airdrop.buy();
+            // = when vesting terms are valid
+            // = * it updates the vesting terms
+            result = az_token_sale_to_airdrop.vesting_terms_update(Some(vesting_terms), None);
+            assert!(result.is_ok());
+            assert_eq!(
+                az_token_sale_to_airdrop.config().vesting_terms,
+                Some(vesting_terms)
+            );
+            // = * it emits a vesting terms update event
+            let event = last_event();
+            match event {
+                Event::VestingTermsUpdate(VestingTermsUpdate {
+                    vesting_terms: event_vesting_terms,
+                    whitelist_vesting_terms,
+                }) => {
+                    assert_eq!(event_vesting_terms, Some(vesting_terms));
+                    assert_eq!(whitelist_vesting_terms, None);
+                }
+                _ => panic!("Expected VestingTermsUpdate event"),
+            }
+            // = when sale has started
+            ink::env::test::set_block_timestamp::<DefaultEnvironment>(MOCK_START);
+            // = * it raises an error
+            result = az_token_sale_to_airdrop.vesting_terms_update(None, None);
             assert_eq!(
                 result,
                 Err(AzTokenSaleToAirdropError::UnprocessableEntity(
-                    "Sold out".to_string()
+                    "Sale has started".to_string()
                 ))
             );
-            // ====== when there is stock available
-            // REST WILL HAVE TO GO INTO INTEGRATION TEST AS IT CALLS AIRDROP SMART CONTRACT
         }
 
         #[ink::test]
@@ -513,6 +4421,26 @@ mod az_token_sale_to_airdrop {
                     .whitelisted,
                 true
             );
+            // = * it emits a WhitelistAdd event
+            match last_event() {
+                Event::WhitelistAdd(WhitelistAdd { address }) => assert_eq!(address, new_address),
+                _ => panic!("Expected WhitelistAdd event"),
+            }
+            // = when in a new phase of the sale
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                az_token_sale_to_airdrop.start,
+            );
+            az_token_sale_to_airdrop
+                .whitelist_add(accounts.frank)
+                .unwrap();
+            // = * it emits the lifecycle event
+            match last_event() {
+                Event::WhitelistPhaseStart(WhitelistPhaseStart { timestamp }) => {
+                    assert_eq!(timestamp, az_token_sale_to_airdrop.start)
+                }
+                _ => panic!("Expected WhitelistPhaseStart event"),
+            }
+            assert_eq!(az_token_sale_to_airdrop.announced_status, Status::Whitelist);
             // = when already on whitelist
             // = * it raises an error
             result = az_token_sale_to_airdrop.whitelist_add(new_address);
@@ -529,6 +4457,65 @@ mod az_token_sale_to_airdrop {
             assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
         }
 
+        #[ink::test]
+        fn test_whitelist_add_many() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            az_token_sale_to_airdrop
+                .whitelist_add(accounts.django)
+                .unwrap();
+            // when called by admin
+            let mut result = az_token_sale_to_airdrop.whitelist_add_many(vec![
+                accounts.django,
+                accounts.frank,
+                accounts.charlie,
+            ]);
+            // * it adds the addresses not on the whitelist
+            // * it skips the addresses already on the whitelist
+            assert_eq!(
+                result,
+                Ok(WhitelistUpdateSummary {
+                    changed: vec![accounts.frank, accounts.charlie],
+                    skipped: vec![accounts.django],
+                })
+            );
+            assert!(az_token_sale_to_airdrop.show(accounts.frank).whitelisted);
+            assert!(az_token_sale_to_airdrop.show(accounts.charlie).whitelisted);
+            // * it emits a WhitelistAdd event for each added address
+            assert_eq!(recorded_events().count(), 3);
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            result = az_token_sale_to_airdrop.whitelist_add_many(vec![accounts.alice]);
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+        }
+
+        #[ink::test]
+        fn test_whitelist_merkle_root_update() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            let whitelist_merkle_root: Option<[u8; 32]> = Some([1; 32]);
+            // when called by admin
+            let mut result =
+                az_token_sale_to_airdrop.whitelist_merkle_root_update(whitelist_merkle_root);
+            // * it updates the whitelist merkle root
+            assert_eq!(result, Ok(whitelist_merkle_root));
+            assert_eq!(
+                az_token_sale_to_airdrop.config().whitelist_merkle_root,
+                whitelist_merkle_root
+            );
+            // * it emits a WhitelistMerkleRootUpdate event
+            match last_event() {
+                Event::WhitelistMerkleRootUpdate(event) => {
+                    assert_eq!(event.whitelist_merkle_root, whitelist_merkle_root)
+                }
+                _ => panic!("Expected WhitelistMerkleRootUpdate event"),
+            }
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            result = az_token_sale_to_airdrop.whitelist_merkle_root_update(None);
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+        }
+
         #[ink::test]
         fn test_whitelist_remove() {
             let (accounts, mut az_token_sale_to_airdrop) = init();
@@ -557,12 +4544,412 @@ mod az_token_sale_to_airdrop {
                     .whitelisted,
                 false
             );
+            // = * it emits a WhitelistRemove event
+            match last_event() {
+                Event::WhitelistRemove(WhitelistRemove { address }) => {
+                    assert_eq!(address, address_to_remove)
+                }
+                _ => panic!("Expected WhitelistRemove event"),
+            }
             // when called by non admin
             // * it raises an error
             set_caller::<DefaultEnvironment>(accounts.charlie);
             result = az_token_sale_to_airdrop.whitelist_remove(address_to_remove);
             assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
         }
+
+        #[ink::test]
+        fn test_whitelist_remove_many() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            az_token_sale_to_airdrop
+                .whitelist_add_many(vec![accounts.django, accounts.frank])
+                .unwrap();
+            // when called by admin
+            let mut result = az_token_sale_to_airdrop.whitelist_remove_many(vec![
+                accounts.django,
+                accounts.charlie,
+                accounts.frank,
+            ]);
+            // * it removes the addresses on the whitelist
+            // * it skips the addresses not on the whitelist
+            assert_eq!(
+                result,
+                Ok(WhitelistUpdateSummary {
+                    changed: vec![accounts.django, accounts.frank],
+                    skipped: vec![accounts.charlie],
+                })
+            );
+            assert!(!az_token_sale_to_airdrop.show(accounts.django).whitelisted);
+            assert!(!az_token_sale_to_airdrop.show(accounts.frank).whitelisted);
+            // when called by non admin
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            // * it raises an error
+            result = az_token_sale_to_airdrop.whitelist_remove_many(vec![accounts.django]);
+            assert_eq!(result, Err(AzTokenSaleToAirdropError::Unauthorised));
+        }
+
+        // === TEST PRIVATE ===
+        #[ink::test]
+        fn test_calculate_out_bonus() {
+            let (_accounts, mut az_token_sale_to_airdrop) = init();
+            let public_start: Timestamp = MOCK_START + MOCK_WHITELIST_DURATION;
+            // when there is no bonus schedule
+            // * it returns zero
+            assert_eq!(
+                az_token_sale_to_airdrop.calculate_out_bonus(MOCK_IN_UNIT, 10_000, MOCK_START),
+                0
+            );
+            // when there is a bonus schedule
+            az_token_sale_to_airdrop.bonus_schedule = Some(BonusSchedule {
+                whitelist_basis_points: 1_000,
+                early_basis_points: 500,
+                early_duration: 100,
+                volume_basis_points: 200,
+                volume_in_threshold: MOCK_IN_UNIT * 10,
+            });
+            // = when in the whitelist phase
+            // = * it applies the whitelist bonus
+            assert_eq!(
+                az_token_sale_to_airdrop.calculate_out_bonus(MOCK_IN_UNIT, 10_000, MOCK_START),
+                1_000
+            );
+            // = when early in the public phase
+            // = * it applies the early bonus
+            assert_eq!(
+                az_token_sale_to_airdrop.calculate_out_bonus(
+                    MOCK_IN_UNIT,
+                    10_000,
+                    public_start + 99
+                ),
+                500
+            );
+            // = when after the early duration
+            // = * it applies no phase bonus
+            assert_eq!(
+                az_token_sale_to_airdrop.calculate_out_bonus(
+                    MOCK_IN_UNIT,
+                    10_000,
+                    public_start + 100
+                ),
+                0
+            );
+            // = when in amount reaches the volume threshold
+            // = * it stacks the volume bonus
+            assert_eq!(
+                az_token_sale_to_airdrop.calculate_out_bonus(MOCK_IN_UNIT * 10, 10_000, MOCK_START),
+                1_200
+            );
+        }
+
+        #[ink::test]
+        fn test_calculate_out_amount() {
+            let (_accounts, mut az_token_sale_to_airdrop) = init();
+            // when in amount is within the first price tier
+            // * it uses the first price tier's out unit
+            assert_eq!(
+                az_token_sale_to_airdrop.calculate_out_amount(MOCK_IN_UNIT * 2, MOCK_START),
+                MOCK_OUT_UNIT * 4
+            );
+            // when in amount crosses price tiers
+            az_token_sale_to_airdrop.in_raised = MOCK_IN_UNIT * 9;
+            // * it splits the in amount across the price tiers
+            assert_eq!(
+                az_token_sale_to_airdrop.calculate_out_amount(MOCK_IN_UNIT * 2, MOCK_START),
+                MOCK_OUT_UNIT * 2 + MOCK_OUT_UNIT * 3 / 2
+            );
+            // when in amount goes past the last price tier
+            az_token_sale_to_airdrop.in_raised = MOCK_IN_UNIT * 19;
+            // * it uses out unit for the remainder
+            assert_eq!(
+                az_token_sale_to_airdrop.calculate_out_amount(MOCK_IN_UNIT * 3, MOCK_START),
+                MOCK_OUT_UNIT * 3 / 2 + MOCK_OUT_UNIT * 2
+            );
+            let (price_tier_index, _) = az_token_sale_to_airdrop.current_price_tier();
+            assert_eq!(price_tier_index, Some(1));
+        }
+
+        #[ink::test]
+        fn test_credit_payer() {
+            let (accounts, az_token_sale_to_airdrop) = init();
+            let mut buyer: Buyer = az_token_sale_to_airdrop.show(accounts.charlie);
+            // when payer has not paid for buyer before
+            // * it adds the payer
+            AzTokenSaleToAirdrop::credit_payer(&mut buyer, accounts.django, MOCK_IN_UNIT);
+            AzTokenSaleToAirdrop::credit_payer(&mut buyer, accounts.eve, MOCK_IN_UNIT * 2);
+            assert_eq!(
+                buyer.payers_in,
+                vec![
+                    (accounts.django, MOCK_IN_UNIT),
+                    (accounts.eve, MOCK_IN_UNIT * 2)
+                ]
+            );
+            // when payer has paid for buyer before
+            // * it adds to the payer's total
+            AzTokenSaleToAirdrop::credit_payer(&mut buyer, accounts.django, MOCK_IN_UNIT * 3);
+            assert_eq!(
+                buyer.payers_in,
+                vec![
+                    (accounts.django, MOCK_IN_UNIT * 4),
+                    (accounts.eve, MOCK_IN_UNIT * 2)
+                ]
+            );
+        }
+
+        #[ink::test]
+        fn test_current_out_unit() {
+            let (_accounts, mut az_token_sale_to_airdrop) = init();
+            // when there is no price schedule
+            // * it returns out unit
+            assert_eq!(
+                az_token_sale_to_airdrop.current_out_unit(MOCK_END),
+                MOCK_OUT_UNIT
+            );
+            // when price schedule is linear
+            az_token_sale_to_airdrop.price_tiers = vec![];
+            az_token_sale_to_airdrop.out_unit_end = Some(MOCK_OUT_UNIT * 2);
+            // * it interpolates between out unit and out unit end
+            assert_eq!(
+                az_token_sale_to_airdrop.current_out_unit(MOCK_START - 1),
+                MOCK_OUT_UNIT
+            );
+            assert_eq!(
+                az_token_sale_to_airdrop.current_out_unit((MOCK_START + MOCK_END) / 2),
+                MOCK_OUT_UNIT * 3 / 2
+            );
+            assert_eq!(
+                az_token_sale_to_airdrop.current_out_unit(MOCK_END + 1),
+                MOCK_OUT_UNIT * 2
+            );
+            // when price schedule is stepped
+            az_token_sale_to_airdrop.price_step_duration = (MOCK_END - MOCK_START) * 2 / 5;
+            // * it only changes at each step
+            assert_eq!(
+                az_token_sale_to_airdrop.current_out_unit((MOCK_START + MOCK_END) / 2),
+                MOCK_OUT_UNIT * 7 / 5
+            );
+            // when price schedule is decreasing
+            az_token_sale_to_airdrop.out_unit_end = Some(MOCK_OUT_UNIT / 2);
+            az_token_sale_to_airdrop.price_step_duration = 0;
+            // * it decreases towards out unit end
+            assert_eq!(
+                az_token_sale_to_airdrop.current_out_unit(MOCK_END),
+                MOCK_OUT_UNIT / 2
+            );
+        }
+
+        #[ink::test]
+        fn test_distribute_proceeds() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            ink::env::test::set_account_balance::<DefaultEnvironment>(
+                ink::env::test::callee::<DefaultEnvironment>(),
+                20_001,
+            );
+            // when proceeds are held
+            // * it splits the amount by basis points, giving the dust to the first payee
+            let mut result = az_token_sale_to_airdrop.distribute_proceeds(None, 10_001);
+            assert_eq!(
+                result,
+                Ok(vec![(mock_treasury(), 7_501), (accounts.charlie, 2_500)])
+            );
+            assert_eq!(
+                az_token_sale_to_airdrop.payee_total(mock_treasury(), None),
+                7_501
+            );
+            // * it credits the shares as withdrawable
+            assert_eq!(
+                az_token_sale_to_airdrop.withdrawable(mock_treasury(), None),
+                7_501
+            );
+            // when proceeds are not held
+            az_token_sale_to_airdrop.in_soft_cap = None;
+            let original_treasury_balance: Balance =
+                ink::env::test::get_account_balance::<DefaultEnvironment>(mock_treasury()).unwrap();
+            // = when there are no payees
+            az_token_sale_to_airdrop.payees = vec![];
+            // = * it sends the amount to the treasury
+            result = az_token_sale_to_airdrop.distribute_proceeds(None, 10_000);
+            assert_eq!(result, Ok(vec![(mock_treasury(), 10_000)]));
+            assert_eq!(
+                ink::env::test::get_account_balance::<DefaultEnvironment>(mock_treasury()).unwrap(),
+                original_treasury_balance + 10_000
+            );
+            assert_eq!(
+                az_token_sale_to_airdrop.payee_total(mock_treasury(), None),
+                17_501
+            );
+            assert_eq!(
+                az_token_sale_to_airdrop.withdrawable(mock_treasury(), None),
+                7_501
+            );
+        }
+
+        #[ink::test]
+        fn test_in_whitelist_phase() {
+            let (_accounts, mut az_token_sale_to_airdrop) = init();
+            // when there is a whitelist duration
+            // * it is true until start + whitelist duration
+            assert!(az_token_sale_to_airdrop.in_whitelist_phase(MOCK_START));
+            assert!(az_token_sale_to_airdrop
+                .in_whitelist_phase(MOCK_START + MOCK_WHITELIST_DURATION - 1));
+            assert!(
+                !az_token_sale_to_airdrop.in_whitelist_phase(MOCK_START + MOCK_WHITELIST_DURATION)
+            );
+            // when there is no whitelist duration
+            az_token_sale_to_airdrop.whitelist_duration = 0;
+            // * it is false
+            assert!(!az_token_sale_to_airdrop.in_whitelist_phase(MOCK_START));
+        }
+
+        #[ink::test]
+        fn test_referral_bonus_amounts() {
+            let (_accounts, mut az_token_sale_to_airdrop) = init();
+            // when there is no referral bonus
+            // * it returns nothing
+            assert_eq!(
+                az_token_sale_to_airdrop
+                    .referral_bonus_amounts(MOCK_IN_UNIT * 2, MOCK_OUT_UNIT * 4),
+                (0, 0)
+            );
+            // when referral bonus is a commission
+            az_token_sale_to_airdrop.referral_bonus = Some(ReferralBonus::In(2_500));
+            // * it carves the commission from the in amount
+            assert_eq!(
+                az_token_sale_to_airdrop
+                    .referral_bonus_amounts(MOCK_IN_UNIT * 2, MOCK_OUT_UNIT * 4),
+                (MOCK_IN_UNIT / 2, 0)
+            );
+            // when referral bonus is extra out tokens
+            az_token_sale_to_airdrop.referral_bonus = Some(ReferralBonus::Out(2_500));
+            // * it adds a share of the out amount
+            assert_eq!(
+                az_token_sale_to_airdrop
+                    .referral_bonus_amounts(MOCK_IN_UNIT * 2, MOCK_OUT_UNIT * 4),
+                (0, MOCK_OUT_UNIT)
+            );
+        }
+
+        #[ink::test]
+        fn test_unwind_referral() {
+            let (accounts, mut az_token_sale_to_airdrop) = init();
+            // when buyer was not referred
+            let mut buyer: Buyer = az_token_sale_to_airdrop.show(accounts.charlie);
+            // * it returns None
+            assert_eq!(az_token_sale_to_airdrop.unwind_referral(&mut buyer), None);
+            // when buyer was referred
+            buyer.referrer = Some(accounts.django);
+            buyer.referred_in = MOCK_IN_UNIT * 4;
+            buyer.referrer_in_commission = MOCK_IN_UNIT;
+            buyer.referrer_out_bonus = MOCK_OUT_UNIT;
+            az_token_sale_to_airdrop.referrers.insert(
+                accounts.django,
+                &Referrer {
+                    in_referred: MOCK_IN_UNIT * 6,
+                    buyers_referred: 2,
+                    in_commission: MOCK_IN_UNIT * 3,
+                    out_bonus: MOCK_OUT_UNIT * 2,
+                },
+            );
+            az_token_sale_to_airdrop
+                .withdrawable
+                .insert((accounts.django, None::<AccountId>), &(MOCK_IN_UNIT * 3));
+            // * it returns the referrer and the out bonus to remove from airdrop
+            assert_eq!(
+                az_token_sale_to_airdrop.unwind_referral(&mut buyer),
+                Some((accounts.django, MOCK_OUT_UNIT))
+            );
+            // * it reverses the referrer's totals
+            assert_eq!(
+                az_token_sale_to_airdrop.referrer(accounts.django),
+                Referrer {
+                    in_referred: MOCK_IN_UNIT * 2,
+                    buyers_referred: 1,
+                    in_commission: MOCK_IN_UNIT * 2,
+                    out_bonus: MOCK_OUT_UNIT,
+                }
+            );
+            // * it removes the commission from the referrer's withdrawable
+            assert_eq!(
+                az_token_sale_to_airdrop.withdrawable(accounts.django, None),
+                MOCK_IN_UNIT * 2
+            );
+            // * it clears the buyer's referral
+            assert_eq!(buyer.referrer, None);
+            assert_eq!(buyer.referred_in, 0);
+            assert_eq!(buyer.referrer_in_commission, 0);
+            assert_eq!(buyer.referrer_out_bonus, 0);
+        }
+
+        #[ink::test]
+        fn test_validate_vesting_terms() {
+            let vesting_terms: VestingTerms = VestingTerms {
+                collectable_at_tge_percentage: 10,
+                cliff_duration: 1_000,
+                vesting_duration: 10_000,
+            };
+            let mut recipient: Recipient = Recipient {
+                total_amount: MOCK_OUT_UNIT * 2,
+                collected: 0,
+                collectable_at_tge_percentage: vesting_terms.collectable_at_tge_percentage,
+                cliff_duration: vesting_terms.cliff_duration,
+                vesting_duration: vesting_terms.vesting_duration,
+            };
+            // when recipient is on the same terms
+            // * it is valid
+            assert_eq!(
+                AzTokenSaleToAirdrop::validate_vesting_terms(&recipient, vesting_terms),
+                Ok(())
+            );
+            // when recipient is on different terms
+            recipient.vesting_duration = vesting_terms.vesting_duration / 2;
+            // * it raises an error
+            assert_eq!(
+                AzTokenSaleToAirdrop::validate_vesting_terms(&recipient, vesting_terms),
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Vesting terms differ from those already applied".to_string()
+                ))
+            );
+        }
+
+        #[ink::test]
+        fn test_vesting_terms_for() {
+            let (_accounts, mut az_token_sale_to_airdrop) = init();
+            // when there are no vesting terms
+            // * it returns none so the airdrop defaults apply
+            assert_eq!(az_token_sale_to_airdrop.vesting_terms_for(true), None);
+            assert_eq!(az_token_sale_to_airdrop.vesting_terms_for(false), None);
+            // when there are only sale vesting terms
+            let vesting_terms: VestingTerms = VestingTerms {
+                collectable_at_tge_percentage: 20,
+                cliff_duration: 1_000,
+                vesting_duration: 10_000,
+            };
+            az_token_sale_to_airdrop.vesting_terms = Some(vesting_terms);
+            // * it returns them for both phases
+            assert_eq!(
+                az_token_sale_to_airdrop.vesting_terms_for(true),
+                Some(vesting_terms)
+            );
+            assert_eq!(
+                az_token_sale_to_airdrop.vesting_terms_for(false),
+                Some(vesting_terms)
+            );
+            // when there are whitelist vesting terms
+            let whitelist_vesting_terms: VestingTerms = VestingTerms {
+                collectable_at_tge_percentage: 10,
+                cliff_duration: 2_000,
+                vesting_duration: 20_000,
+            };
+            az_token_sale_to_airdrop.whitelist_vesting_terms = Some(whitelist_vesting_terms);
+            // * it returns them for the whitelist phase only
+            assert_eq!(
+                az_token_sale_to_airdrop.vesting_terms_for(true),
+                Some(whitelist_vesting_terms)
+            );
+            assert_eq!(
+                az_token_sale_to_airdrop.vesting_terms_for(false),
+                Some(vesting_terms)
+            );
+        }
     }
     // The main purpose of the e2e tests are to test the interactions with az groups contract
     #[cfg(all(test, feature = "e2e-tests"))]
@@ -583,20 +4970,138 @@ mod az_token_sale_to_airdrop {
         const MOCK_END: Timestamp = 2_708_669_904_756;
         const MOCK_WHITELIST_DURATION: Timestamp = 0;
         const MOCK_IN_TARGET: Balance = 50_000_000_000_000_000;
+        const MOCK_MIN_IN_PER_BUYER: Balance = 0;
+        const MOCK_MAX_IN_PER_BUYER: Option<Balance> = None;
+        const MOCK_WHITELIST_IN_TARGET: Option<Balance> = None;
+        const MOCK_IN_SOFT_CAP: Option<Balance> = None;
+        const MOCK_OUT_UNIT_END: Option<Balance> = None;
+        const MOCK_PRICE_STEP_DURATION: Timestamp = 0;
+        const MOCK_IN_TOKEN: Option<AccountId> = None;
+        const MOCK_HOLD_PROCEEDS: bool = false;
+
+        // ms to configure a sale that starts in the future, as most admin messages require it
+        const MOCK_START_DELAY: Timestamp = 30_000;
 
         // Airdrop
         const MOCK_AIRDROP_START: Timestamp = 2_708_669_904_756;
 
-        // Token
-        const MOCK_AMOUNT: Balance = 100_000_000_000_000_000_000;
+        // Token
+        const MOCK_AMOUNT: Balance = 100_000_000_000_000_000_000;
+
+        // === TYPES ===
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+        type E2EClient = ink_e2e::Client<ink_e2e::PolkadotConfig, ink::env::DefaultEnvironment>;
+
+        // === HELPERS ===
+        fn account_id(k: Keypair) -> AccountId {
+            AccountId::try_from(k.public_key().to_account_id().as_ref())
+                .expect("account keyring has a valid account id")
+        }
+
+        // Instantiates an airdrop smart contract holding all of the token
+        async fn instantiate_airdrop(client: &mut E2EClient, token_id: AccountId) -> AccountId {
+            let airdrop_constructor =
+                AzAirdropRef::new(token_id, MOCK_AIRDROP_START, 20, 0, 31_556_952_000);
+            let airdrop_id: AccountId = client
+                .instantiate(
+                    "az_airdrop",
+                    &ink_e2e::alice(),
+                    airdrop_constructor,
+                    0,
+                    None,
+                )
+                .await
+                .expect("Airdrop instantiate failed")
+                .account_id;
+            let transfer_message = build_message::<ButtonRef>(token_id)
+                .call(|button| button.transfer(airdrop_id, MOCK_AMOUNT, vec![]));
+            client
+                .call(&ink_e2e::alice(), transfer_message, 0, None)
+                .await
+                .expect("Transfer to airdrop failed");
+
+            airdrop_id
+        }
+
+        // Instantiates a token with alice holding the whole supply
+        async fn instantiate_token(client: &mut E2EClient) -> AccountId {
+            let token_constructor = ButtonRef::new(
+                MOCK_AMOUNT,
+                Some("DIBS".to_string()),
+                Some("DIBS".to_string()),
+                12,
+            );
+            client
+                .instantiate("az_button", &ink_e2e::alice(), token_constructor, 0, None)
+                .await
+                .expect("Token instantiate failed")
+                .account_id
+        }
+
+        // Instantiates a token sale administered by alice and adds it as sub admin of the airdrop
+        async fn instantiate_token_sale(
+            client: &mut E2EClient,
+            airdrop_id: AccountId,
+            start: Timestamp,
+            in_soft_cap: Option<Balance>,
+            in_token: Option<AccountId>,
+        ) -> AccountId {
+            let token_sale_constructor = AzTokenSaleToAirdropRef::new(
+                airdrop_id,
+                MOCK_IN_UNIT,
+                MOCK_OUT_UNIT,
+                start,
+                MOCK_END,
+                MOCK_WHITELIST_DURATION,
+                MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                in_soft_cap,
+                vec![],
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                in_token,
+                account_id(ink_e2e::alice()),
+                vec![],
+                MOCK_HOLD_PROCEEDS,
+            );
+            let token_sale_id: AccountId = client
+                .instantiate(
+                    "az_token_sale_to_airdrop",
+                    &ink_e2e::alice(),
+                    token_sale_constructor,
+                    0,
+                    None,
+                )
+                .await
+                .expect("Token sale instantiate failed")
+                .account_id;
+            let sub_admins_add_message = build_message::<AzAirdropRef>(airdrop_id)
+                .call(|airdrop| airdrop.sub_admins_add(token_sale_id));
+            client
+                .call(&ink_e2e::alice(), sub_admins_add_message, 0, None)
+                .await
+                .expect("Sub admins add failed");
+
+            token_sale_id
+        }
 
-        // === TYPES ===
-        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+        fn now() -> Timestamp {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as Timestamp
+        }
 
-        // === HELPERS ===
-        fn account_id(k: Keypair) -> AccountId {
-            AccountId::try_from(k.public_key().to_account_id().as_ref())
-                .expect("account keyring has a valid account id")
+        // The node stamps blocks with the wall clock, so waiting past start opens the sale
+        fn wait_until(timestamp: Timestamp) {
+            let current_timestamp: Timestamp = now();
+            if timestamp >= current_timestamp {
+                std::thread::sleep(std::time::Duration::from_millis(
+                    timestamp - current_timestamp + 1_000,
+                ));
+            }
         }
 
         // === TEST HANDLES ===
@@ -661,6 +5166,17 @@ mod az_token_sale_to_airdrop {
                 MOCK_END,
                 MOCK_WHITELIST_DURATION,
                 MOCK_IN_TARGET,
+                MOCK_MIN_IN_PER_BUYER,
+                MOCK_MAX_IN_PER_BUYER,
+                MOCK_WHITELIST_IN_TARGET,
+                MOCK_IN_SOFT_CAP,
+                vec![],
+                MOCK_OUT_UNIT_END,
+                MOCK_PRICE_STEP_DURATION,
+                MOCK_IN_TOKEN,
+                alice_account_id,
+                vec![],
+                MOCK_HOLD_PROCEEDS,
             );
             let token_sale_id: AccountId = client
                 .instantiate(
@@ -694,7 +5210,7 @@ mod az_token_sale_to_airdrop {
                 client.balance(alice_account_id).await.unwrap();
             let original_bob_azero_balance: Balance = client.balance(bob_account_id).await.unwrap();
             let buy_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
-                .call(|token_sale| token_sale.buy());
+                .call(|token_sale| token_sale.buy(None));
             let buy_result = client
                 .call(&ink_e2e::bob(), buy_message, MOCK_IN_UNIT, None)
                 .await
@@ -727,14 +5243,14 @@ mod az_token_sale_to_airdrop {
                 .await
                 .return_value();
             assert_eq!(result.total_in, MOCK_IN_UNIT);
-            // ==== * it sends the in_amount to the admin
+            // ==== * it sends the in_amount to the treasury
             assert_eq!(
                 client.balance(alice_account_id).await.unwrap(),
                 original_alice_azero_balance + MOCK_IN_UNIT
             );
             // ==== when there is only enough stock to partially fill order
             let buy_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
-                .call(|token_sale| token_sale.buy());
+                .call(|token_sale| token_sale.buy(None));
             let buy_result = client
                 .call(&ink_e2e::bob(), buy_message, MOCK_IN_TARGET, None)
                 .await
@@ -770,7 +5286,7 @@ mod az_token_sale_to_airdrop {
                 .await
                 .return_value();
             assert_eq!(result.total_in, MOCK_IN_TARGET);
-            // ==== * it sends the in_amount to the admin
+            // ==== * it sends the in_amount to the treasury
             assert_eq!(
                 client.balance(alice_account_id).await.unwrap(),
                 original_alice_azero_balance + MOCK_IN_TARGET
@@ -783,5 +5299,548 @@ mod az_token_sale_to_airdrop {
 
             Ok(())
         }
+
+        #[ink_e2e::test]
+        async fn test_buy_with_in_token(mut client: ::ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let alice_account_id: AccountId = account_id(ink_e2e::alice());
+            let bob_account_id: AccountId = account_id(ink_e2e::bob());
+            let token_id: AccountId = instantiate_token(&mut client).await;
+            let airdrop_id: AccountId = instantiate_airdrop(&mut client, token_id).await;
+            let in_token_id: AccountId = instantiate_token(&mut client).await;
+            let token_sale_id: AccountId = instantiate_token_sale(
+                &mut client,
+                airdrop_id,
+                MOCK_START,
+                MOCK_IN_SOFT_CAP,
+                Some(in_token_id),
+            )
+            .await;
+            let transfer_message = build_message::<ButtonRef>(in_token_id)
+                .call(|button| button.transfer(bob_account_id, MOCK_IN_UNIT * 2, vec![]));
+            client
+                .call(&ink_e2e::alice(), transfer_message, 0, None)
+                .await
+                .expect("Transfer to bob failed");
+
+            // when sale has started
+            // = when buyer has not approved the in amount
+            // = * it raises an error
+            let buy_with_in_token_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.buy_with_in_token(MOCK_IN_UNIT * 2, None));
+            let result = client
+                .call_dry_run(&ink_e2e::bob(), &buy_with_in_token_message, 0, None)
+                .await
+                .return_value();
+            assert!(result.is_err());
+            // = when buyer has approved the in amount
+            let approve_message = build_message::<ButtonRef>(in_token_id)
+                .call(|button| button.approve(token_sale_id, MOCK_IN_UNIT * 2));
+            client
+                .call(&ink_e2e::bob(), approve_message, 0, None)
+                .await
+                .expect("Approve failed");
+            // = * it returns the in amount, out amount and out bonus
+            let result = client
+                .call(&ink_e2e::bob(), buy_with_in_token_message, 0, None)
+                .await
+                .expect("Buy with in token failed")
+                .return_value();
+            assert_eq!(result, Ok((MOCK_IN_UNIT * 2, MOCK_OUT_UNIT * 2, 0)));
+            // = * it increases the recipient amount on airdrop by the out amount
+            let airdrop_show_message = build_message::<AzAirdropRef>(airdrop_id)
+                .call(|airdrop| airdrop.show(bob_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &airdrop_show_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result.unwrap().total_amount, MOCK_OUT_UNIT * 2);
+            // = * it transfers the in amount from the buyer to the treasury
+            let balance_of_message = build_message::<ButtonRef>(in_token_id)
+                .call(|button| button.balance_of(bob_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result, 0);
+            let balance_of_message = build_message::<ButtonRef>(in_token_id)
+                .call(|button| button.balance_of(alice_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result, MOCK_AMOUNT);
+            // = * it increases the in_raised by the in amount
+            let config_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.config());
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &config_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result.in_raised, MOCK_IN_UNIT * 2);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn test_buy_with_payment_asset(mut client: ::ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let alice_account_id: AccountId = account_id(ink_e2e::alice());
+            let bob_account_id: AccountId = account_id(ink_e2e::bob());
+            let token_id: AccountId = instantiate_token(&mut client).await;
+            let airdrop_id: AccountId = instantiate_airdrop(&mut client, token_id).await;
+            let payment_token_id: AccountId = instantiate_token(&mut client).await;
+            let start: Timestamp = now() + MOCK_START_DELAY;
+            let token_sale_id: AccountId =
+                instantiate_token_sale(&mut client, airdrop_id, start, MOCK_IN_SOFT_CAP, None)
+                    .await;
+            // accept a payment asset worth half as much as AZERO per unit, up to 6 units of it
+            let payment_asset_add_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| {
+                    token_sale.payment_asset_add(
+                        payment_token_id,
+                        MOCK_IN_UNIT * 2,
+                        Some(MOCK_IN_UNIT * 6),
+                    )
+                });
+            client
+                .call(&ink_e2e::alice(), payment_asset_add_message, 0, None)
+                .await
+                .expect("Payment asset add failed");
+            // give bob payment tokens and let the sale spend them
+            let transfer_message = build_message::<ButtonRef>(payment_token_id)
+                .call(|button| button.transfer(bob_account_id, MOCK_IN_UNIT * 10, vec![]));
+            client
+                .call(&ink_e2e::alice(), transfer_message, 0, None)
+                .await
+                .expect("Transfer to bob failed");
+            let approve_message = build_message::<ButtonRef>(payment_token_id)
+                .call(|button| button.approve(token_sale_id, MOCK_IN_UNIT * 10));
+            client
+                .call(&ink_e2e::bob(), approve_message, 0, None)
+                .await
+                .expect("Approve failed");
+            wait_until(start);
+
+            // when sale has started
+            // = when there is enough of the payment asset's target to fill full order
+            // = * it returns the payment asset amount accepted, out amount and out bonus
+            let buy_with_payment_asset_message =
+                build_message::<AzTokenSaleToAirdropRef>(token_sale_id).call(|token_sale| {
+                    token_sale.buy_with_payment_asset(payment_token_id, MOCK_IN_UNIT * 4, None)
+                });
+            let result = client
+                .call(&ink_e2e::bob(), buy_with_payment_asset_message, 0, None)
+                .await
+                .expect("Buy with payment asset failed")
+                .return_value();
+            assert_eq!(result, Ok((MOCK_IN_UNIT * 4, MOCK_OUT_UNIT * 2, 0)));
+            // = when there is only enough of the payment asset's target to partially fill order
+            // = * it only accepts what is left of the payment asset's target
+            let buy_with_payment_asset_message =
+                build_message::<AzTokenSaleToAirdropRef>(token_sale_id).call(|token_sale| {
+                    token_sale.buy_with_payment_asset(payment_token_id, MOCK_IN_UNIT * 6, None)
+                });
+            let result = client
+                .call(&ink_e2e::bob(), buy_with_payment_asset_message, 0, None)
+                .await
+                .expect("Buy with payment asset failed")
+                .return_value();
+            assert_eq!(result, Ok((MOCK_IN_UNIT * 2, MOCK_OUT_UNIT, 0)));
+            // = * it increases the recipient amount on airdrop by the out amounts
+            let airdrop_show_message = build_message::<AzAirdropRef>(airdrop_id)
+                .call(|airdrop| airdrop.show(bob_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &airdrop_show_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result.unwrap().total_amount, MOCK_OUT_UNIT * 3);
+            // = * it records the base equivalent and the payment asset amount on the buyer
+            let buyer_show_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.show(bob_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &buyer_show_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result.total_in, MOCK_IN_UNIT * 3);
+            assert_eq!(
+                result.payment_assets_in,
+                vec![(payment_token_id, MOCK_IN_UNIT * 6)]
+            );
+            // = * it only transfers the accepted amounts from the buyer to the treasury
+            let balance_of_message = build_message::<ButtonRef>(payment_token_id)
+                .call(|button| button.balance_of(bob_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result, MOCK_IN_UNIT * 4);
+            let balance_of_message = build_message::<ButtonRef>(payment_token_id)
+                .call(|button| button.balance_of(alice_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result, MOCK_AMOUNT - MOCK_IN_UNIT * 4);
+            // = when the payment asset has sold out
+            // = * it raises an error
+            let buy_with_payment_asset_message =
+                build_message::<AzTokenSaleToAirdropRef>(token_sale_id).call(|token_sale| {
+                    token_sale.buy_with_payment_asset(payment_token_id, MOCK_IN_UNIT * 2, None)
+                });
+            let result = client
+                .call_dry_run(&ink_e2e::bob(), &buy_with_payment_asset_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Payment asset sold out".to_string()
+                ))
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn test_buy_with_referrer(mut client: ::ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let alice_account_id: AccountId = account_id(ink_e2e::alice());
+            let bob_account_id: AccountId = account_id(ink_e2e::bob());
+            let charlie_account_id: AccountId = account_id(ink_e2e::charlie());
+            let dave_account_id: AccountId = account_id(ink_e2e::dave());
+            let token_id: AccountId = instantiate_token(&mut client).await;
+            let airdrop_id: AccountId = instantiate_airdrop(&mut client, token_id).await;
+            let start: Timestamp = now() + MOCK_START_DELAY;
+            let token_sale_id: AccountId =
+                instantiate_token_sale(&mut client, airdrop_id, start, MOCK_IN_SOFT_CAP, None)
+                    .await;
+            // give referrers a 10% commission
+            let referral_bonus_update_message =
+                build_message::<AzTokenSaleToAirdropRef>(token_sale_id).call(|token_sale| {
+                    token_sale.referral_bonus_update(Some(ReferralBonus::In(1_000)))
+                });
+            client
+                .call(&ink_e2e::alice(), referral_bonus_update_message, 0, None)
+                .await
+                .expect("Referral bonus update failed");
+            wait_until(start);
+
+            // when sale has started
+            // = when buyer has not been referred before
+            let original_alice_azero_balance: Balance =
+                client.balance(alice_account_id).await.unwrap();
+            let original_charlie_azero_balance: Balance =
+                client.balance(charlie_account_id).await.unwrap();
+            let buy_with_referrer_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.buy_with_referrer(charlie_account_id, None));
+            let result = client
+                .call(
+                    &ink_e2e::bob(),
+                    buy_with_referrer_message,
+                    MOCK_IN_UNIT * 10,
+                    None,
+                )
+                .await
+                .expect("Buy with referrer failed")
+                .return_value();
+            // = * it registers the out amount for the buyer as usual
+            assert_eq!(result, Ok((MOCK_IN_UNIT * 10, MOCK_OUT_UNIT * 10, 0)));
+            let airdrop_show_message = build_message::<AzAirdropRef>(airdrop_id)
+                .call(|airdrop| airdrop.show(bob_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &airdrop_show_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result.unwrap().total_amount, MOCK_OUT_UNIT * 10);
+            // = * it sends the commission to the referrer and the rest to the treasury
+            assert_eq!(
+                client.balance(charlie_account_id).await.unwrap(),
+                original_charlie_azero_balance + MOCK_IN_UNIT
+            );
+            assert_eq!(
+                client.balance(alice_account_id).await.unwrap(),
+                original_alice_azero_balance + MOCK_IN_UNIT * 9
+            );
+            // = * it records the referral on the buyer and the referrer
+            let buyer_show_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.show(bob_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &buyer_show_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result.referrer, Some(charlie_account_id));
+            assert_eq!(result.referrer_in_commission, MOCK_IN_UNIT);
+            let referrer_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.referrer(charlie_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &referrer_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(
+                result,
+                Referrer {
+                    in_referred: MOCK_IN_UNIT * 10,
+                    buyers_referred: 1,
+                    in_commission: MOCK_IN_UNIT,
+                    out_bonus: 0,
+                }
+            );
+            // = when buyer has been referred by someone else before
+            // = * it raises an error
+            let buy_with_referrer_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.buy_with_referrer(dave_account_id, None));
+            let result = client
+                .call_dry_run(
+                    &ink_e2e::bob(),
+                    &buy_with_referrer_message,
+                    MOCK_IN_UNIT,
+                    None,
+                )
+                .await
+                .return_value();
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Buyer already has a different referrer".to_string()
+                ))
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn test_claim_refund(mut client: ::ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let bob_account_id: AccountId = account_id(ink_e2e::bob());
+            let charlie_account_id: AccountId = account_id(ink_e2e::charlie());
+            let dave_account_id: AccountId = account_id(ink_e2e::dave());
+            let token_id: AccountId = instantiate_token(&mut client).await;
+            let airdrop_id: AccountId = instantiate_airdrop(&mut client, token_id).await;
+            let payment_token_id: AccountId = instantiate_token(&mut client).await;
+            let start: Timestamp = now() + MOCK_START_DELAY;
+            let token_sale_id: AccountId =
+                instantiate_token_sale(&mut client, airdrop_id, start, Some(MOCK_IN_TARGET), None)
+                    .await;
+            // accept a payment asset worth half as much as AZERO per unit
+            let payment_asset_add_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| {
+                    token_sale.payment_asset_add(payment_token_id, MOCK_IN_UNIT * 2, None)
+                });
+            client
+                .call(&ink_e2e::alice(), payment_asset_add_message, 0, None)
+                .await
+                .expect("Payment asset add failed");
+            // give referrers 10% extra out tokens
+            let referral_bonus_update_message =
+                build_message::<AzTokenSaleToAirdropRef>(token_sale_id).call(|token_sale| {
+                    token_sale.referral_bonus_update(Some(ReferralBonus::Out(1_000)))
+                });
+            client
+                .call(&ink_e2e::alice(), referral_bonus_update_message, 0, None)
+                .await
+                .expect("Referral bonus update failed");
+            // give bob payment tokens and let the sale spend them
+            let transfer_message = build_message::<ButtonRef>(payment_token_id)
+                .call(|button| button.transfer(bob_account_id, MOCK_IN_UNIT * 4, vec![]));
+            client
+                .call(&ink_e2e::alice(), transfer_message, 0, None)
+                .await
+                .expect("Transfer to bob failed");
+            let approve_message = build_message::<ButtonRef>(payment_token_id)
+                .call(|button| button.approve(token_sale_id, MOCK_IN_UNIT * 4));
+            client
+                .call(&ink_e2e::bob(), approve_message, 0, None)
+                .await
+                .expect("Approve failed");
+            wait_until(start);
+            // bob buys with a referrer, with the payment asset, and dave buys for bob
+            let buy_with_referrer_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.buy_with_referrer(charlie_account_id, None));
+            client
+                .call(
+                    &ink_e2e::bob(),
+                    buy_with_referrer_message,
+                    MOCK_IN_UNIT * 2,
+                    None,
+                )
+                .await
+                .expect("Buy with referrer failed");
+            let buy_with_payment_asset_message =
+                build_message::<AzTokenSaleToAirdropRef>(token_sale_id).call(|token_sale| {
+                    token_sale.buy_with_payment_asset(payment_token_id, MOCK_IN_UNIT * 4, None)
+                });
+            client
+                .call(&ink_e2e::bob(), buy_with_payment_asset_message, 0, None)
+                .await
+                .expect("Buy with payment asset failed");
+            let buy_for_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.buy_for(bob_account_id, None));
+            client
+                .call(&ink_e2e::dave(), buy_for_message, MOCK_IN_UNIT, None)
+                .await
+                .expect("Buy for failed");
+
+            // when sale has been finalized without reaching the soft cap
+            let finalize_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.finalize());
+            client
+                .call(&ink_e2e::alice(), finalize_message, 0, None)
+                .await
+                .expect("Finalize failed");
+            let original_bob_azero_balance: Balance = client.balance(bob_account_id).await.unwrap();
+            let original_dave_azero_balance: Balance =
+                client.balance(dave_account_id).await.unwrap();
+            // = * it refunds the buyer's total in amount and removes their whole out amount
+            let claim_refund_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.claim_refund());
+            let result = client
+                .call(&ink_e2e::bob(), claim_refund_message, 0, None)
+                .await
+                .expect("Claim refund failed")
+                .return_value();
+            assert_eq!(result, Ok((MOCK_IN_UNIT * 5, MOCK_OUT_UNIT * 5)));
+            // = * it removes the out amount from the buyer on airdrop
+            let airdrop_show_message = build_message::<AzAirdropRef>(airdrop_id)
+                .call(|airdrop| airdrop.show(bob_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &airdrop_show_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result.map_or(0, |recipient| recipient.total_amount), 0);
+            // = * it removes the referrer's out bonus on airdrop
+            let airdrop_show_message = build_message::<AzAirdropRef>(airdrop_id)
+                .call(|airdrop| airdrop.show(charlie_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &airdrop_show_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result.map_or(0, |recipient| recipient.total_amount), 0);
+            // = * it unwinds the referrer's totals
+            let referrer_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.referrer(charlie_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &referrer_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result, Referrer::default());
+            // = * it returns the payment asset paid
+            let balance_of_message = build_message::<ButtonRef>(payment_token_id)
+                .call(|button| button.balance_of(bob_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result, MOCK_IN_UNIT * 4);
+            // = * it returns what was paid on the buyer's behalf to the payer
+            assert_eq!(
+                client.balance(dave_account_id).await.unwrap(),
+                original_dave_azero_balance + MOCK_IN_UNIT
+            );
+            // = * it returns the rest in AZERO to the buyer
+            let bob_azero_balance: Balance = client.balance(bob_account_id).await.unwrap();
+            assert!(bob_azero_balance > original_bob_azero_balance + MOCK_IN_UNIT);
+            assert!(bob_azero_balance <= original_bob_azero_balance + MOCK_IN_UNIT * 2);
+            // = * it resets the buyer
+            let buyer_show_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.show(bob_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &buyer_show_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result.total_in, 0);
+            assert_eq!(result.referrer, None);
+            // = * it records the amount refunded
+            let config_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.config());
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &config_message, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result.in_refunded, MOCK_IN_UNIT * 5);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn test_vesting_terms_update(mut client: ::ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let bob_account_id: AccountId = account_id(ink_e2e::bob());
+            let dave_account_id: AccountId = account_id(ink_e2e::dave());
+            let token_id: AccountId = instantiate_token(&mut client).await;
+            let airdrop_id: AccountId = instantiate_airdrop(&mut client, token_id).await;
+            let start: Timestamp = now() + MOCK_START_DELAY;
+            let token_sale_id: AccountId =
+                instantiate_token_sale(&mut client, airdrop_id, start, MOCK_IN_SOFT_CAP, None)
+                    .await;
+            let vesting_terms: VestingTerms = VestingTerms {
+                collectable_at_tge_percentage: 10,
+                cliff_duration: 60_000,
+                vesting_duration: 600_000,
+            };
+            let vesting_terms_update_message =
+                build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                    .call(|token_sale| token_sale.vesting_terms_update(Some(vesting_terms), None));
+            client
+                .call(&ink_e2e::alice(), vesting_terms_update_message, 0, None)
+                .await
+                .expect("Vesting terms update failed");
+            // dave already has an allocation on the airdrop's default terms, e.g. from another round
+            let recipient_add_message = build_message::<AzAirdropRef>(airdrop_id)
+                .call(|airdrop| airdrop.recipient_add(dave_account_id, MOCK_OUT_UNIT, None));
+            client
+                .call(&ink_e2e::alice(), recipient_add_message, 0, None)
+                .await
+                .expect("Recipient add failed");
+            wait_until(start);
+
+            // when buyer is new to the airdrop
+            let buy_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.buy(None));
+            client
+                .call(&ink_e2e::bob(), buy_message, MOCK_IN_UNIT, None)
+                .await
+                .expect("Buy failed");
+            // * it sets the recipient's vesting on airdrop to the sale's terms
+            let airdrop_show_message = build_message::<AzAirdropRef>(airdrop_id)
+                .call(|airdrop| airdrop.show(bob_account_id));
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &airdrop_show_message, 0, None)
+                .await
+                .return_value()
+                .unwrap();
+            assert_eq!(result.total_amount, MOCK_OUT_UNIT);
+            assert_eq!(
+                result.collectable_at_tge_percentage,
+                vesting_terms.collectable_at_tge_percentage
+            );
+            assert_eq!(result.cliff_duration, vesting_terms.cliff_duration);
+            assert_eq!(result.vesting_duration, vesting_terms.vesting_duration);
+            // when buyer already has an allocation on the same terms
+            // * it adds to the allocation
+            let buy_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.buy(None));
+            client
+                .call(&ink_e2e::bob(), buy_message, MOCK_IN_UNIT, None)
+                .await
+                .expect("Buy failed");
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &airdrop_show_message, 0, None)
+                .await
+                .return_value()
+                .unwrap();
+            assert_eq!(result.total_amount, MOCK_OUT_UNIT * 2);
+            // when buyer already has an allocation on different terms
+            // * it raises an error
+            let buy_message = build_message::<AzTokenSaleToAirdropRef>(token_sale_id)
+                .call(|token_sale| token_sale.buy(None));
+            let result = client
+                .call_dry_run(&ink_e2e::dave(), &buy_message, MOCK_IN_UNIT, None)
+                .await
+                .return_value();
+            assert_eq!(
+                result,
+                Err(AzTokenSaleToAirdropError::UnprocessableEntity(
+                    "Vesting terms differ from those already applied".to_string()
+                ))
+            );
+
+            Ok(())
+        }
     }
 }